@@ -0,0 +1,151 @@
+use std::future::Future;
+use std::io;
+use std::pin::pin;
+
+use futures_util::{Stream, StreamExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::chunks::Items;
+use crate::{TryIntoXML, XMLChunks, XMLError, XML};
+
+/// Writes XML to a [`tokio::io::AsyncWrite`] one fragment at a time, so a large document never
+/// has to be formatted into a single `String`.
+///
+/// Every fragment is a separate write, so wrap unbuffered writers like sockets and files in a
+/// [`tokio::io::BufWriter`], and [`flush`](AsyncXMLWriter::flush) once done.
+#[derive(Debug)]
+pub struct AsyncXMLWriter<W> {
+    inner: W,
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncXMLWriter<W> {
+    pub const fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// # Errors
+    /// Returns any error raised by the underlying writer.
+    pub async fn write_chunks(&mut self, chunks: XMLChunks<'_>) -> io::Result<()> {
+        for chunk in chunks {
+            self.inner.write_all(chunk.as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    /// See [`AsyncXMLWriter::write_chunks`]
+    pub async fn write_xml(&mut self, xml: &XML) -> io::Result<()> {
+        self.write_chunks(xml.chunks()).await
+    }
+
+    /// Converts `value` up front, so the returned future doesn't borrow it.
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidData`] error if `value` can't be converted, as well as
+    /// any error raised by the underlying writer.
+    pub fn write<T: TryIntoXML + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> impl Future<Output = io::Result<()>> + Send + '_ {
+        let xml = value.try_to_xml().map_err(invalid_data);
+        async move { self.write_chunks(xml?.into_chunks()).await }
+    }
+
+    /// Writes `root` with every item of `items` appended as a child, after any children `root`
+    /// already has. Items are serialized as they arrive, declaring whichever namespaces `root`
+    /// doesn't.
+    ///
+    /// A root that ends up without children is written as an empty element, `<root/>`, as its
+    /// `Display` output would be.
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if `root` is not a node or an item has an
+    /// XML declaration, which can only start a document, an [`io::ErrorKind::InvalidData`] error
+    /// if an item can't be converted, as well as any error raised by the underlying writer.
+    pub async fn write_stream<S>(&mut self, root: &XML, items: S) -> io::Result<()>
+    where
+        S: Stream + Send,
+        S::Item: TryIntoXML + Send,
+    {
+        let (declaration, node) = match root {
+            XML::Node(node) => (None, node),
+            XML::Declaration(declaration, node) => (Some(declaration), node),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Streamed children need a root node",
+                ))
+            }
+        };
+
+        let declared = node.namespaces();
+
+        let mut start = declaration.map(ToString::to_string).unwrap_or_default();
+        // Writing into a String can't fail.
+        _ = node.write_start_tag(&mut start, &declared);
+        // The start tag is only closed once there's a child, as `/>` may still end it.
+        let mut open = !node.data.is_empty();
+        if open {
+            start.push('>');
+        }
+        self.inner.write_all(start.as_bytes()).await?;
+
+        self.write_chunks(XMLChunks::from_items(
+            Items::Borrowed(node.data.iter()),
+            false,
+        ))
+        .await?;
+
+        let mut items = pin!(items);
+        while let Some(item) = items.next().await {
+            let xml = item.try_to_xml().map_err(invalid_data)?;
+            if has_declaration(&xml) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Streamed children can't have an XML declaration",
+                ));
+            }
+            if !open {
+                self.inner.write_all(b">").await?;
+                open = true;
+            }
+            self.write_chunks(xml.into_chunks().declared(declared.clone()))
+                .await?;
+        }
+
+        let mut end = String::new();
+        if open {
+            _ = node.write_end_tag(&mut end);
+        } else {
+            end.push_str("/>");
+        }
+        self.inner.write_all(end.as_bytes()).await
+    }
+
+    /// # Errors
+    /// Returns any error raised by the underlying writer.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().await
+    }
+}
+
+fn invalid_data(error: XMLError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Whether `xml` would write an XML declaration at the top level.
+fn has_declaration(xml: &XML) -> bool {
+    match xml {
+        XML::Declaration(..) => true,
+        XML::Container(nodes) => nodes.iter().any(has_declaration),
+        _ => false,
+    }
+}
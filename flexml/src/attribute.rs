@@ -1,9 +1,18 @@
-use std::fmt::Display;
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+    hash::BuildHasher,
+};
 
 use log::warn;
 
 use crate::{XMLError, XMLNamespace, XMLNamespaces};
 
+/// The name `xsi:type` attributes register the XML Schema instance namespace under.
+pub const XSI_NAMESPACE: &str = "xsi";
+const XSI_URI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
 #[derive(Debug, Clone)]
 pub struct XMLAttribute {
     pub(crate) key: String,
@@ -20,6 +29,33 @@ impl XMLAttribute {
         }
     }
 
+    /// An attribute holding `value`'s [`ToXMLAttributeValue`], or `None` if it has none.
+    pub fn from_value<T: Display, V: ToXMLAttributeValue + ?Sized>(
+        key: T,
+        value: &V,
+    ) -> Option<Self> {
+        value
+            .to_xml_attribute_value()
+            .map(|value| Self::new(key, &value))
+    }
+
+    /// An `xsi:type="value"` attribute, registering the XML Schema instance namespace under the
+    /// `xsi` alias if it isn't registered yet.
+    ///
+    /// # Errors
+    /// Will return an error if the global namespaces lock is poisoned.
+    pub fn xsi_type<V: Display + ?Sized>(value: &V) -> Result<Self, XMLError> {
+        XMLNamespaces::insert_with_alias(XSI_NAMESPACE, "xsi", XSI_URI)?;
+        Self::new("type", &value).namespace(XSI_NAMESPACE)
+    }
+
+    pub(crate) fn ns_tag(&self) -> String {
+        self.namespace.as_ref().map_or_else(
+            || self.key.clone(),
+            |ns| format!("{}:{}", ns.alias, self.key),
+        )
+    }
+
     /// # Errors
     /// See [`set_namespace`]
     #[inline]
@@ -42,3 +78,138 @@ impl XMLAttribute {
         Ok(())
     }
 }
+
+/// A reusable group of attributes, like an XSD `attributeGroup`. An `#[attributes]` field of such
+/// a type spreads its attributes onto the element derived with `ToXML`.
+pub trait ToXMLAttributes {
+    /// # Errors
+    /// Returns an error if a namespace isn't registered, or the global namespaces lock is
+    /// poisoned.
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError>;
+}
+
+impl ToXMLAttributes for XMLAttribute {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        Ok(vec![self.clone()])
+    }
+}
+
+impl ToXMLAttributes for Vec<XMLAttribute> {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        Ok(self.clone())
+    }
+}
+
+impl<T: ToXMLAttributes> ToXMLAttributes for Option<T> {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        self.as_ref()
+            .map_or_else(|| Ok(Vec::new()), ToXMLAttributes::to_xml_attributes)
+    }
+}
+
+impl<T: ToXMLAttributes + ?Sized> ToXMLAttributes for &T {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        (**self).to_xml_attributes()
+    }
+}
+
+/// The text of an attribute, used for every `#[attribute]` field. `None` leaves the attribute
+/// out entirely.
+///
+/// Types that can't implement it, like foreign `Display` types, can be formatted with
+/// `#[attribute(display)]` instead.
+pub trait ToXMLAttributeValue {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>>;
+}
+
+impl ToXMLAttributeValue for str {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl ToXMLAttributeValue for String {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+macro_rules! display_attribute_value {
+    ($($t:ty),*) => {
+        $(
+            impl ToXMLAttributeValue for $t {
+                fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+                    Some(Cow::Owned(self.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+display_attribute_value!(
+    bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+impl<T: ToXMLAttributeValue> ToXMLAttributeValue for Option<T> {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        self.as_ref()
+            .and_then(ToXMLAttributeValue::to_xml_attribute_value)
+    }
+}
+
+/// Joins the values of `items` into a space-separated list, skipping items without a value.
+/// Lists left empty have no value either.
+fn list_value<'a>(items: impl IntoIterator<Item = Option<Cow<'a, str>>>) -> Option<Cow<'a, str>> {
+    let items = items.into_iter().flatten().collect::<Vec<_>>();
+    (!items.is_empty()).then(|| Cow::Owned(items.join(" ")))
+}
+
+/// A space-separated list, like `xs:list`. Items without a value are skipped, and an empty list
+/// leaves the attribute out.
+impl<T: ToXMLAttributeValue> ToXMLAttributeValue for [T] {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        list_value(self.iter().map(ToXMLAttributeValue::to_xml_attribute_value))
+    }
+}
+
+impl<T: ToXMLAttributeValue, const N: usize> ToXMLAttributeValue for [T; N] {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        self.as_slice().to_xml_attribute_value()
+    }
+}
+
+impl<T: ToXMLAttributeValue> ToXMLAttributeValue for Vec<T> {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        self.as_slice().to_xml_attribute_value()
+    }
+}
+
+impl<T: ToXMLAttributeValue> ToXMLAttributeValue for BTreeSet<T> {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        list_value(self.iter().map(ToXMLAttributeValue::to_xml_attribute_value))
+    }
+}
+
+/// Items are sorted by their value, so the list doesn't depend on the set's iteration order.
+impl<T: ToXMLAttributeValue, S: BuildHasher> ToXMLAttributeValue for HashSet<T, S> {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        let mut items = self
+            .iter()
+            .filter_map(ToXMLAttributeValue::to_xml_attribute_value)
+            .collect::<Vec<_>>();
+        items.sort_unstable();
+        list_value(items.into_iter().map(Some))
+    }
+}
+
+impl<T: ToXMLAttributeValue + ?Sized> ToXMLAttributeValue for &T {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        (**self).to_xml_attribute_value()
+    }
+}
+
+impl<T: ToXMLAttributeValue + ToOwned + ?Sized> ToXMLAttributeValue for Cow<'_, T> {
+    fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().to_xml_attribute_value()
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt::{self, Write};
+
+use crate::node::XMLNode;
+use crate::{XMLAttribute, XMLNamespace};
+
+/// Canonical XML output modes, for byte-stable output suitable for hashing, caching and
+/// signature digests.
+///
+/// Both modes drop the XML declaration, expand empty elements (`<a></a>`), sort namespace
+/// declarations by prefix and attributes by namespace URI then name, and escape text and
+/// attribute values - including tabs and line breaks in attributes, so their whitespace survives
+/// attribute value normalization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canonicalization {
+    /// [Canonical XML 1.0](https://www.w3.org/TR/xml-c14n/). Every namespace used in the
+    /// document is declared on its top level element.
+    C14N,
+    /// [Exclusive XML Canonicalization 1.0](https://www.w3.org/TR/xml-exc-c14n/). Namespaces
+    /// are declared on the elements that visibly use them, so a subtree canonicalizes the same
+    /// regardless of the document it's taken from.
+    ExclusiveC14N,
+}
+
+impl Canonicalization {
+    /// The namespaces `node` declares, given those already declared by its output ancestors.
+    pub(crate) fn namespaces(
+        self,
+        node: &XMLNode,
+        top: bool,
+        in_scope: impl Fn(&XMLNamespace) -> bool,
+    ) -> Vec<XMLNamespace> {
+        let mut namespaces = match self {
+            Self::C14N if top => node.namespaces(),
+            Self::C14N => Vec::new(),
+            Self::ExclusiveC14N => node
+                .namespace
+                .iter()
+                .chain(node.attributes.iter().filter_map(|a| a.namespace.as_ref()))
+                .cloned()
+                .collect(),
+        };
+
+        namespaces.retain(|ns| !in_scope(ns));
+        namespaces.sort();
+        namespaces.dedup();
+        namespaces
+    }
+}
+
+pub fn write_start_tag<W: Write>(
+    node: &XMLNode,
+    w: &mut W,
+    namespaces: &[XMLNamespace],
+) -> fmt::Result {
+    write!(w, "<{}", node.ns_tag())?;
+
+    for namespace in namespaces {
+        write!(
+            w,
+            r#" xmlns:{}="{}""#,
+            namespace.alias,
+            escape_attribute(&namespace.uri)
+                .as_deref()
+                .unwrap_or(&namespace.uri)
+        )?;
+    }
+
+    let uri = |attribute: &XMLAttribute| {
+        attribute
+            .namespace
+            .as_ref()
+            .map_or(String::new(), |ns| ns.uri.clone())
+    };
+    let mut attributes = node.attributes.iter().collect::<Vec<_>>();
+    attributes.sort_by(|a, b| uri(a).cmp(&uri(b)).then_with(|| a.key.cmp(&b.key)));
+
+    for attribute in attributes {
+        write!(
+            w,
+            r#" {}="{}""#,
+            attribute.ns_tag(),
+            escape_attribute(&attribute.value)
+                .as_deref()
+                .unwrap_or(&attribute.value)
+        )?;
+    }
+
+    write!(w, ">")
+}
+
+/// Returns the escaped text, or `None` if nothing needed escaping.
+pub fn escape_text(text: &str) -> Option<String> {
+    escape(text, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    })
+}
+
+fn escape_attribute(value: &str) -> Option<String> {
+    escape(value, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '"' => Some("&quot;"),
+        '\t' => Some("&#x9;"),
+        '\n' => Some("&#xA;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    })
+}
+
+fn escape(value: &str, replacement: impl Fn(char) -> Option<&'static str>) -> Option<String> {
+    if !value.chars().any(|c| replacement(c).is_some()) {
+        return None;
+    }
+
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match replacement(c) {
+            Some(r) => escaped.push_str(r),
+            None => escaped.push(c),
+        }
+    }
+    Some(escaped)
+}
@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase, ToTrainCase,
+    ToUpperCamelCase,
+};
+
+use crate::XMLError;
+
+/// A naming scheme for node names, applied with [`crate::XML::case`]. The built-in schemes are
+/// provided by [heck](https://docs.rs/heck).
+///
+/// Also parsed from the strings accepted by `#[case]` in the derive, such as `"snake_case"` or
+/// `"PascalCase"`.
+#[derive(Debug, Clone, Copy)]
+pub enum Case {
+    Kebab,
+    LowerCamel,
+    ShoutyKebab,
+    ShoutySnake,
+    Snake,
+    Train,
+    UpperCamel,
+    /// A conversion heck doesn't cover, such as one that keeps acronyms like `XMLId` intact.
+    Custom(fn(&str) -> String),
+}
+
+impl Case {
+    #[must_use]
+    pub fn apply(self, input: &str) -> String {
+        match self {
+            Self::Kebab => input.to_kebab_case(),
+            Self::LowerCamel => input.to_lower_camel_case(),
+            Self::ShoutyKebab => input.to_shouty_kebab_case(),
+            Self::ShoutySnake => input.to_shouty_snake_case(),
+            Self::Snake => input.to_snake_case(),
+            Self::Train => input.to_train_case(),
+            Self::UpperCamel => input.to_upper_camel_case(),
+            Self::Custom(f) => f(input),
+        }
+    }
+}
+
+impl FromStr for Case {
+    type Err = XMLError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "KebabCase" | "kebab-case" | "kebab-kase" => Self::Kebab,
+            "LowerCamelCase" | "lowerCamelCase" => Self::LowerCamel,
+            "ShoutyKebabCase" | "SHOUTY-KEBAB-CASE" => Self::ShoutyKebab,
+            // "snek" - What chicanery, what shenanigans - and dare I say it - what tomfoolery!
+            "ShoutySnakeCase" | "SHOUTY_SNAKE_CASE" | "ShoutySnekCase" | "SHOUTY_SNEK_CASE" => {
+                Self::ShoutySnake
+            }
+            "SnakeCase" | "snake_case" | "SnekCase" | "snek_case" => Self::Snake,
+
+            "TitleCase" | "Title Case" => {
+                return Err(XMLError::Case(
+                    "XML does not allow the 'Title Case' casing scheme.".to_string(),
+                ))
+            }
+            "TrainCase" | "Train-Case" => Self::Train,
+            "UpperCamelCase" | "PascalCase" => Self::UpperCamel,
+            r => return Err(XMLError::Case(format!("Unknown case '{r}'"))),
+        })
+    }
+}
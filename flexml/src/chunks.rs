@@ -0,0 +1,201 @@
+use std::{borrow::Cow, slice, vec};
+
+use crate::canonical::{self, Canonicalization};
+use crate::declaration::XMLDeclaration;
+use crate::node::XMLNode;
+use crate::{XMLNamespace, XML};
+
+/// An iterator over the serialized fragments of an [`XML`] tree.
+///
+/// Every item is a piece of markup - a start tag, a run of text or an end tag - so a document
+/// can be written out as it is walked instead of being formatted into a single `String` first.
+/// Concatenating every fragment gives the same output as the `Display` implementation of [`XML`].
+#[derive(Debug)]
+pub struct XMLChunks<'a> {
+    stack: Vec<Frame<'a>>,
+    declared: Vec<XMLNamespace>,
+    canonicalization: Option<Canonicalization>,
+}
+
+#[derive(Debug)]
+enum Frame<'a> {
+    /// Remaining siblings, and whether they sit at the top level of the output.
+    Items(Items<'a>, bool),
+    /// An end tag, and the namespaces declared by its start tag.
+    Close(String, Vec<XMLNamespace>),
+}
+
+#[derive(Debug)]
+pub enum Items<'a> {
+    Borrowed(slice::Iter<'a, XML>),
+    Owned(vec::IntoIter<XML>),
+}
+
+impl<'a> Iterator for Items<'a> {
+    type Item = Cow<'a, XML>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Borrowed(iter) => iter.next().map(Cow::Borrowed),
+            Self::Owned(iter) => iter.next().map(Cow::Owned),
+        }
+    }
+}
+
+impl<'a> XMLChunks<'a> {
+    pub(crate) fn new(xml: &'a XML) -> Self {
+        Self::from_items(Items::Borrowed(slice::from_ref(xml).iter()), true)
+    }
+
+    pub(crate) fn new_owned(xml: XML) -> Self {
+        Self::from_items(Items::Owned(vec![xml].into_iter()), true)
+    }
+
+    pub(crate) fn from_items(items: Items<'a>, top: bool) -> Self {
+        Self {
+            stack: vec![Frame::Items(items, top)],
+            declared: Vec::new(),
+            canonicalization: None,
+        }
+    }
+
+    /// Produces canonical XML instead. See [`Canonicalization`].
+    #[must_use]
+    pub const fn canonicalize(mut self, mode: Canonicalization) -> Self {
+        self.canonicalization = Some(mode);
+        self
+    }
+
+    /// Treats `namespaces` as already declared by an enclosing element, so top level nodes
+    /// won't declare them again.
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub(crate) fn declared(mut self, namespaces: Vec<XMLNamespace>) -> Self {
+        self.declared = namespaces;
+        self
+    }
+
+    /// Renders the start tag of `node` and schedules its end tag.
+    fn enter(
+        &mut self,
+        node: &XMLNode,
+        declaration: Option<&XMLDeclaration>,
+        top: bool,
+    ) -> Cow<'a, str> {
+        // Writing into a String can't fail.
+        let mut end = String::new();
+        _ = node.write_end_tag(&mut end);
+
+        if let Some(mode) = self.canonicalization {
+            let namespaces = mode.namespaces(node, top, |ns| self.in_scope(ns));
+
+            let mut start = String::new();
+            _ = canonical::write_start_tag(node, &mut start, &namespaces);
+            self.stack.push(Frame::Close(end, namespaces));
+
+            return Cow::Owned(start);
+        }
+
+        let namespaces = if top {
+            node.namespaces()
+                .into_iter()
+                .filter(|ns| !self.declared.contains(ns))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut start = declaration.map(ToString::to_string).unwrap_or_default();
+        _ = node.write_start_tag(&mut start, &namespaces);
+
+        if node.data.is_empty() {
+            start.push_str("/>");
+        } else {
+            start.push('>');
+            self.stack.push(Frame::Close(end, namespaces));
+        }
+
+        Cow::Owned(start)
+    }
+
+    fn in_scope(&self, namespace: &XMLNamespace) -> bool {
+        self.declared.contains(namespace)
+            || self.stack.iter().any(
+                |frame| matches!(frame, Frame::Close(_, declared) if declared.contains(namespace)),
+            )
+    }
+
+    fn text(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        if self.canonicalization.is_none() {
+            return text;
+        }
+        canonical::escape_text(&text).map_or(text, Cow::Owned)
+    }
+}
+
+impl<'a> Iterator for XMLChunks<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (item, top) = match self.stack.last_mut()? {
+                Frame::Items(items, top) => {
+                    let top = *top;
+                    let Some(item) = items.next() else {
+                        self.stack.pop();
+                        continue;
+                    };
+                    (item, top)
+                }
+                Frame::Close(end, _) => {
+                    let end = std::mem::take(end);
+                    self.stack.pop();
+                    return Some(Cow::Owned(end));
+                }
+            };
+
+            match item {
+                Cow::Borrowed(xml) => match xml {
+                    XML::Text(text) => return Some(self.text(Cow::Borrowed(text))),
+                    XML::Container(nodes) => {
+                        self.stack
+                            .push(Frame::Items(Items::Borrowed(nodes.iter()), top));
+                    }
+                    XML::Node(node) => {
+                        let start = self.enter(node, None, top);
+                        self.stack
+                            .push(Frame::Items(Items::Borrowed(node.data.iter()), false));
+                        return Some(start);
+                    }
+                    XML::Declaration(declaration, node) => {
+                        let start = self.enter(node, top.then_some(declaration), top);
+                        self.stack
+                            .push(Frame::Items(Items::Borrowed(node.data.iter()), false));
+                        return Some(start);
+                    }
+                    XML::None => {}
+                },
+                Cow::Owned(xml) => match xml {
+                    XML::Text(text) => return Some(self.text(Cow::Owned(text))),
+                    XML::Container(nodes) => {
+                        self.stack
+                            .push(Frame::Items(Items::Owned(nodes.into_iter()), top));
+                    }
+                    XML::Node(node) => {
+                        let start = self.enter(&node, None, top);
+                        self.stack
+                            .push(Frame::Items(Items::Owned(node.data.into_iter()), false));
+                        return Some(start);
+                    }
+                    XML::Declaration(declaration, node) => {
+                        let start = self.enter(&node, top.then_some(&declaration), top);
+                        self.stack
+                            .push(Frame::Items(Items::Owned(node.data.into_iter()), false));
+                        return Some(start);
+                    }
+                    XML::None => {}
+                },
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use log::error;
+use log::{error, warn};
 
 use crate::attribute::XMLAttribute;
 use crate::declaration::XMLDeclaration;
-use crate::{IntoXML, XMLError, XMLNamespace};
+use crate::{
+    Canonicalization, Case, IntoXML, TryIntoXML, XMLChunks, XMLError, XMLNamespace, XMLReader,
+};
 
 use crate::node::XMLNode;
 
@@ -49,6 +51,20 @@ impl XML {
         }
     }
 
+    #[must_use]
+    #[inline]
+    pub fn attributes<I: IntoIterator<Item = XMLAttribute>>(mut self, attributes: I) -> Self {
+        self.add_attributes(attributes);
+        self
+    }
+
+    #[inline]
+    pub fn add_attributes<I: IntoIterator<Item = XMLAttribute>>(&mut self, attributes: I) {
+        for attribute in attributes {
+            self.add_attribute(attribute);
+        }
+    }
+
     #[must_use]
     #[inline]
     pub fn name<T: Display>(mut self, name: T) -> Self {
@@ -71,17 +87,17 @@ impl XML {
     }
 
     #[must_use]
-    pub fn case<T: Display>(mut self, case: T) -> Self {
+    pub fn case(mut self, case: Case) -> Self {
         self.set_case(case);
         self
     }
 
-    pub fn set_case<T: Display>(&mut self, case: T) {
+    pub fn set_case(&mut self, case: Case) {
         match self {
             Self::Node(ref mut node) => node.set_case(case),
             Self::Container(ref mut nodes) => {
                 for node in nodes.iter_mut() {
-                    node.set_case(case.to_string());
+                    node.set_case(case);
                 }
             }
             _ => {}
@@ -195,16 +211,65 @@ impl XML {
         self.add_data(nodes);
     }
 
+    /// Moves the attributes and children of `other` into this node, dropping the tag of
+    /// `other` itself. An attribute replaces one of the same name, and is dropped if this is a
+    /// container. The items of containers and text are added as children unchanged.
+    #[must_use]
+    #[inline]
+    pub fn merge(mut self, other: Self) -> Self {
+        self.add_merged(other);
+        self
+    }
+
+    pub fn add_merged(&mut self, other: Self) {
+        match other {
+            Self::Node(node) | Self::Declaration(_, node) => {
+                for attribute in node.attributes {
+                    self.add_merged_attribute(attribute);
+                }
+                for datum in node.data {
+                    self.add_datum(datum);
+                }
+            }
+            Self::Container(items) => {
+                for item in items {
+                    self.add_datum(item);
+                }
+            }
+            text @ Self::Text(_) => self.add_datum(text),
+            Self::None => {}
+        }
+    }
+
+    /// Adds `attribute`, replacing any attribute of the same name. Containers have no tag to
+    /// hold it, and copying it onto their children would change what it applies to.
+    fn add_merged_attribute(&mut self, attribute: XMLAttribute) {
+        match self {
+            Self::Node(node) | Self::Declaration(_, node) => {
+                node.attributes.retain(|a| {
+                    a.key != attribute.key
+                        || a.namespace.as_ref().map(|ns| &ns.uri)
+                            != attribute.namespace.as_ref().map(|ns| &ns.uri)
+                });
+                node.add_attribute(attribute);
+            }
+            _ => warn!(
+                "Attribute {} merged into an untagged value. Ignoring.",
+                attribute.key
+            ),
+        }
+    }
+
     #[must_use]
     #[inline]
-    pub fn text(mut self, text: &String) -> Self {
-        self.add_datum(text.to_xml());
+    pub fn text<T: Display + ?Sized>(mut self, text: &T) -> Self {
+        self.add_text(text);
         self
     }
 
     #[inline]
-    pub fn add_text(&mut self, text: &String) {
-        self.add_datum(text.to_xml());
+    pub fn add_text<T: Display + ?Sized>(&mut self, text: &T) {
+        self.add_datum(Self::Text(text.to_string()));
     }
 
     #[must_use]
@@ -223,6 +288,50 @@ impl XML {
         }
     }
 
+    /// Serializes the tree one fragment at a time. See [`XMLChunks`].
+    #[must_use]
+    pub fn chunks(&self) -> XMLChunks<'_> {
+        XMLChunks::new(self)
+    }
+
+    /// Like [`XML::chunks`], but consumes the tree, releasing each node once it's written.
+    #[must_use]
+    pub fn into_chunks(self) -> XMLChunks<'static> {
+        XMLChunks::new_owned(self)
+    }
+
+    /// Serializes the tree as canonical XML. Canonicalizing a subtree on its own is done by
+    /// calling this on that subtree, which with [`Canonicalization::ExclusiveC14N`] gives the
+    /// same output as it would inside its document.
+    #[must_use]
+    pub fn to_canonical_string(&self, mode: Canonicalization) -> String {
+        self.chunks().canonicalize(mode).collect()
+    }
+
+    /// Returns an [`std::io::Read`] that serializes the tree as it's read. See [`XMLReader`].
+    #[must_use]
+    pub fn reader(&self) -> XMLReader<'_> {
+        self.chunks().into()
+    }
+
+    /// Like [`XML::reader`], but consumes the tree.
+    #[must_use]
+    pub fn into_reader(self) -> XMLReader<'static> {
+        self.into_chunks().into()
+    }
+
+    /// Writes the document to `writer` without formatting it into a `String` first.
+    ///
+    /// # Errors
+    /// Returns any error raised by the underlying writer.
+    #[cfg(feature = "async")]
+    pub async fn write_to_async<W>(&self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: tokio::io::AsyncWrite + Unpin + Send,
+    {
+        crate::AsyncXMLWriter::new(writer).write_xml(self).await
+    }
+
     /// # Errors
     /// See [`XMLNode::sub_fmt`]
     pub fn sub_fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -266,8 +375,8 @@ impl From<XMLNode> for XML {
     }
 }
 
-impl IntoXML for XML {
-    fn to_xml(&self) -> XML {
-        self.to_owned()
+impl TryIntoXML for XML {
+    fn try_to_xml(&self) -> Result<Self, XMLError> {
+        Ok(self.to_owned())
     }
 }
@@ -0,0 +1,398 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+use crate::node::XMLNode;
+use crate::{XMLError, XML};
+
+/// Options for [`XML::semantic_eq_with`] and [`xml_diff_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XMLDiffOptions {
+    /// Trims text content and ignores whitespace-only text, such as indentation between elements.
+    pub ignore_whitespace: bool,
+}
+
+impl XMLDiffOptions {
+    #[must_use]
+    pub const fn ignore_whitespace(mut self, ignore_whitespace: bool) -> Self {
+        self.ignore_whitespace = ignore_whitespace;
+        self
+    }
+}
+
+/// A single difference between two trees, found by [`xml_diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XMLDifference {
+    /// Location of the difference, such as `/root/Node[2]`. Elements are identified by local
+    /// name and their position among same-named siblings of the left tree.
+    pub path: String,
+    pub kind: XMLDifferenceKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XMLDifferenceKind {
+    Name {
+        left: String,
+        right: String,
+    },
+    /// Namespaces are compared by URI, so differing prefixes alone aren't a difference.
+    Namespace {
+        left: Option<String>,
+        right: Option<String>,
+    },
+    AttributeOnlyInLeft(String),
+    AttributeOnlyInRight(String),
+    AttributeValue {
+        name: String,
+        left: String,
+        right: String,
+    },
+    Text {
+        left: String,
+        right: String,
+    },
+    /// An element on one side where the other has text.
+    Content {
+        left: String,
+        right: String,
+    },
+    ChildOnlyInLeft(String),
+    ChildOnlyInRight(String),
+}
+
+impl Display for XMLDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{path}: ")?;
+        match &self.kind {
+            XMLDifferenceKind::Name { left, right } => {
+                write!(f, "element name <{left}> != <{right}>")
+            }
+            XMLDifferenceKind::Namespace { left, right } => write!(
+                f,
+                "namespace {} != {}",
+                left.as_deref().unwrap_or("(none)"),
+                right.as_deref().unwrap_or("(none)")
+            ),
+            XMLDifferenceKind::AttributeOnlyInLeft(name) => {
+                write!(f, "attribute {name} only in left")
+            }
+            XMLDifferenceKind::AttributeOnlyInRight(name) => {
+                write!(f, "attribute {name} only in right")
+            }
+            XMLDifferenceKind::AttributeValue { name, left, right } => {
+                write!(f, "attribute {name} {left:?} != {right:?}")
+            }
+            XMLDifferenceKind::Text { left, right } => write!(f, "text {left:?} != {right:?}"),
+            XMLDifferenceKind::Content { left, right } => write!(f, "{left} != {right}"),
+            XMLDifferenceKind::ChildOnlyInLeft(child) => write!(f, "{child} only in left"),
+            XMLDifferenceKind::ChildOnlyInRight(child) => write!(f, "{child} only in right"),
+        }
+    }
+}
+
+/// Lists every difference between two trees, or nothing if they're semantically equal.
+///
+/// Attribute order, namespace prefixes, declarations and how text is split across nodes are
+/// ignored.
+#[must_use]
+pub fn xml_diff(left: &XML, right: &XML) -> Vec<XMLDifference> {
+    xml_diff_with(left, right, XMLDiffOptions::default())
+}
+
+/// See [`xml_diff`].
+#[must_use]
+pub fn xml_diff_with(left: &XML, right: &XML, options: XMLDiffOptions) -> Vec<XMLDifference> {
+    let mut differences = Vec::new();
+    compare_items(
+        "",
+        &items(std::slice::from_ref(left), options),
+        &items(std::slice::from_ref(right), options),
+        options,
+        &mut differences,
+    );
+    differences
+}
+
+impl XML {
+    /// Whether `self` and `other` are equal under the rules of [`xml_diff`].
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        self.semantic_eq_with(other, XMLDiffOptions::default())
+    }
+
+    #[must_use]
+    pub fn semantic_eq_with(&self, other: &Self, options: XMLDiffOptions) -> bool {
+        xml_diff_with(self, other, options).is_empty()
+    }
+}
+
+/// Values accepted by [`crate::assert_xml_eq`]: trees, and markup to be parsed.
+pub trait AsXML {
+    /// # Errors
+    /// Returns an error if markup can't be parsed.
+    fn as_xml(&self) -> Result<Cow<'_, XML>, XMLError>;
+}
+
+impl AsXML for XML {
+    fn as_xml(&self) -> Result<Cow<'_, XML>, XMLError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl AsXML for str {
+    fn as_xml(&self) -> Result<Cow<'_, XML>, XMLError> {
+        crate::parse::parse(self).map(Cow::Owned)
+    }
+}
+
+impl AsXML for String {
+    fn as_xml(&self) -> Result<Cow<'_, XML>, XMLError> {
+        self.as_str().as_xml()
+    }
+}
+
+impl<T: AsXML + ?Sized> AsXML for &T {
+    fn as_xml(&self) -> Result<Cow<'_, XML>, XMLError> {
+        (**self).as_xml()
+    }
+}
+
+/// Asserts that two trees are semantically equal, listing every difference on failure. Either
+/// side may be an [`XML`] tree or a markup string. Options may be passed as a third argument.
+///
+/// ```
+/// use flexml::{assert_xml_eq, XMLAttribute, XML};
+///
+/// let xml = XML::new("root")
+///     .attribute(XMLAttribute::new("a", &1))
+///     .attribute(XMLAttribute::new("b", &2));
+///
+/// assert_xml_eq!(r#"<root b="2" a="1"/>"#, xml);
+/// ```
+#[macro_export]
+macro_rules! assert_xml_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_xml_eq!($left, $right, $crate::XMLDiffOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {
+        $crate::__assert_xml_eq(&$left, &$right, $options)
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __assert_xml_eq<L: AsXML + ?Sized, R: AsXML + ?Sized>(
+    left: &L,
+    right: &R,
+    options: XMLDiffOptions,
+) {
+    let left = left
+        .as_xml()
+        .unwrap_or_else(|e| panic!("Could not parse left side of assert_xml_eq - {e}"));
+    let right = right
+        .as_xml()
+        .unwrap_or_else(|e| panic!("Could not parse right side of assert_xml_eq - {e}"));
+
+    let differences = xml_diff_with(&left, &right, options);
+    if !differences.is_empty() {
+        let differences = differences
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join("\n  ");
+        panic!(
+            "assertion `left == right` failed: XML differs\n  {differences}\n  left: {left}\n right: {right}"
+        );
+    }
+}
+
+enum Item<'a> {
+    Element(&'a XMLNode),
+    Text(String),
+}
+
+impl Item<'_> {
+    fn describe(&self) -> String {
+        match self {
+            Self::Element(node) => format!("element <{}>", node.name),
+            Self::Text(text) => format!("text {text:?}"),
+        }
+    }
+}
+
+/// Flattens containers and declarations and merges adjacent text.
+fn items(data: &[XML], options: XMLDiffOptions) -> Vec<Item<'_>> {
+    fn flatten<'a>(data: &'a [XML], out: &mut Vec<Item<'a>>) {
+        for datum in data {
+            match datum {
+                XML::Node(node) | XML::Declaration(_, node) => out.push(Item::Element(node)),
+                XML::Text(text) => {
+                    if let Some(Item::Text(last)) = out.last_mut() {
+                        last.push_str(text);
+                    } else {
+                        out.push(Item::Text(text.clone()));
+                    }
+                }
+                XML::Container(nodes) => flatten(nodes, out),
+                XML::None => {}
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    flatten(data, &mut out);
+
+    if options.ignore_whitespace {
+        out.retain_mut(|item| match item {
+            Item::Element(_) => true,
+            Item::Text(text) => {
+                *text = text.trim().to_string();
+                !text.is_empty()
+            }
+        });
+    }
+    out
+}
+
+fn compare_items(
+    path: &str,
+    left: &[Item],
+    right: &[Item],
+    options: XMLDiffOptions,
+    out: &mut Vec<XMLDifference>,
+) {
+    let mut difference = |path: String, kind| out.push(XMLDifference { path, kind });
+    let mut nested = Vec::new();
+
+    for i in 0..left.len().max(right.len()) {
+        let (item, siblings) = left
+            .get(i)
+            .map_or_else(|| (right.get(i), right), |item| (Some(item), left));
+        let item_path = match item {
+            Some(Item::Element(node)) => {
+                let position = siblings
+                    .get(..=i)
+                    .unwrap_or(siblings)
+                    .iter()
+                    .filter(|item| matches!(item, Item::Element(n) if n.name == node.name))
+                    .count()
+                    .max(1);
+                format!("{path}/{}[{position}]", node.name)
+            }
+            _ => format!("{path}/text()"),
+        };
+
+        match (left.get(i), right.get(i)) {
+            (Some(Item::Element(l)), Some(Item::Element(r))) => nested.push((item_path, *l, *r)),
+            (Some(Item::Text(l)), Some(Item::Text(r))) => {
+                if l != r {
+                    difference(
+                        item_path,
+                        XMLDifferenceKind::Text {
+                            left: l.clone(),
+                            right: r.clone(),
+                        },
+                    );
+                }
+            }
+            (Some(l), Some(r)) => difference(
+                item_path,
+                XMLDifferenceKind::Content {
+                    left: l.describe(),
+                    right: r.describe(),
+                },
+            ),
+            (Some(l), None) => {
+                difference(item_path, XMLDifferenceKind::ChildOnlyInLeft(l.describe()));
+            }
+            (None, Some(r)) => {
+                difference(item_path, XMLDifferenceKind::ChildOnlyInRight(r.describe()));
+            }
+            (None, None) => {}
+        }
+    }
+
+    for (path, left, right) in nested {
+        compare_nodes(&path, left, right, options, out);
+    }
+}
+
+fn compare_nodes(
+    path: &str,
+    left: &XMLNode,
+    right: &XMLNode,
+    options: XMLDiffOptions,
+    out: &mut Vec<XMLDifference>,
+) {
+    let mut difference = |kind| {
+        out.push(XMLDifference {
+            path: path.to_string(),
+            kind,
+        });
+    };
+
+    if left.name != right.name {
+        difference(XMLDifferenceKind::Name {
+            left: left.name.clone(),
+            right: right.name.clone(),
+        });
+    }
+
+    let left_uri = left.namespace.as_ref().map(|ns| ns.uri.clone());
+    let right_uri = right.namespace.as_ref().map(|ns| ns.uri.clone());
+    if left_uri != right_uri {
+        difference(XMLDifferenceKind::Namespace {
+            left: left_uri,
+            right: right_uri,
+        });
+    }
+
+    let attributes = |node: &XMLNode| {
+        let mut attributes = node
+            .attributes
+            .iter()
+            .map(|a| {
+                let name = a
+                    .namespace
+                    .as_ref()
+                    .map_or_else(|| a.key.clone(), |ns| format!("{{{}}}{}", ns.uri, a.key));
+                (name, a.value.clone())
+            })
+            .collect::<Vec<(String, String)>>();
+        attributes.sort();
+        attributes
+    };
+    let left_attributes = attributes(left);
+    let right_attributes = attributes(right);
+
+    for (name, value) in &left_attributes {
+        match right_attributes.iter().find(|(n, _)| n == name) {
+            Some((_, right_value)) if right_value != value => {
+                difference(XMLDifferenceKind::AttributeValue {
+                    name: name.clone(),
+                    left: value.clone(),
+                    right: right_value.clone(),
+                });
+            }
+            Some(_) => {}
+            None => difference(XMLDifferenceKind::AttributeOnlyInLeft(name.clone())),
+        }
+    }
+    for (name, _) in &right_attributes {
+        if !left_attributes.iter().any(|(n, _)| n == name) {
+            difference(XMLDifferenceKind::AttributeOnlyInRight(name.clone()));
+        }
+    }
+
+    compare_items(
+        path,
+        &items(&left.data, options),
+        &items(&right.data, options),
+        options,
+        out,
+    );
+}
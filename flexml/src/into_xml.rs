@@ -1,135 +1,137 @@
 use std::borrow::Cow;
 
-use crate::XML;
+use crate::{XMLError, XML};
 
+/// Fallible conversion into an [`XML`] tree. This is the trait to implement, and the one
+/// derived by `ToXML` - [`IntoXML`] comes with it.
+pub trait TryIntoXML {
+    /// # Errors
+    /// Returns an error if a namespace isn't registered, or the global namespaces lock is
+    /// poisoned.
+    fn try_to_xml(&self) -> Result<XML, XMLError>;
+}
+
+/// Infallible conversion into an [`XML`] tree, implemented for every [`TryIntoXML`] type.
 pub trait IntoXML {
+    /// # Panics
+    /// Panics if [`TryIntoXML::try_to_xml`] fails.
     fn to_xml(&self) -> XML;
 }
 
-impl<T: IntoXML> IntoXML for Option<T> {
+impl<T: TryIntoXML + ?Sized> IntoXML for T {
     fn to_xml(&self) -> XML {
-        self.as_ref().map_or(XML::None, IntoXML::to_xml)
+        self.try_to_xml().expect("Failed to convert to XML")
     }
 }
 
-impl<T: IntoXML> IntoXML for Vec<T> {
-    fn to_xml(&self) -> XML {
-        let mut xml = XML::new_untagged();
-        for val in self {
-            xml.add_datum(val.to_xml());
-        }
-        xml
+impl<T: TryIntoXML> TryIntoXML for Option<T> {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        self.as_ref().map_or(Ok(XML::None), TryIntoXML::try_to_xml)
     }
 }
 
-impl<T: IntoXML> IntoXML for &[T] {
-    fn to_xml(&self) -> XML {
-        let mut xml = XML::new_untagged();
-        for val in *self {
-            xml.add_datum(val.to_xml());
-        }
-        xml
+impl<T: TryIntoXML> TryIntoXML for Vec<T> {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        self.as_slice().try_to_xml()
     }
 }
 
-impl<T: IntoXML + ToOwned> IntoXML for Cow<'_, T> {
-    fn to_xml(&self) -> XML {
-        self.as_ref().to_xml()
+impl<T: TryIntoXML> TryIntoXML for [T] {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        let mut xml = XML::new_untagged();
+        for val in self {
+            xml.add_datum(val.try_to_xml()?);
+        }
+        Ok(xml)
     }
 }
 
-impl<T: IntoXML + ToOwned> IntoXML for Cow<'_, [T]>
-where
-    [T]: ToOwned,
-{
-    fn to_xml(&self) -> XML {
-        self.as_ref().to_xml()
+impl<T: TryIntoXML + ?Sized> TryIntoXML for &T {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        (**self).try_to_xml()
     }
 }
 
-impl IntoXML for bool {
-    fn to_xml(&self) -> XML {
-        match self {
-            true => "true".to_xml(),
-            false => "false".to_xml(),
-        }
+impl<T: TryIntoXML + ToOwned + ?Sized> TryIntoXML for Cow<'_, T> {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        self.as_ref().try_to_xml()
     }
 }
 
-impl IntoXML for &str {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for bool {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for str {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for str {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for String {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.clone())
+impl TryIntoXML for String {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.clone()))
     }
 }
 
-impl IntoXML for u8 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for u8 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for u16 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for u16 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for u32 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for u32 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for u64 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for u64 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for u128 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for u128 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for i8 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for i8 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for i16 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for i16 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for i32 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for i32 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for i64 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for i64 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
 
-impl IntoXML for i128 {
-    fn to_xml(&self) -> XML {
-        XML::Text(self.to_string())
+impl TryIntoXML for i128 {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Text(self.to_string()))
     }
 }
@@ -1,26 +1,42 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "async")]
+mod async_writer;
 mod attribute;
+mod canonical;
+mod case;
+mod chunks;
 mod data;
 mod declaration;
+mod diff;
 mod into_xml;
+mod map;
 mod namespace;
 mod node;
+mod parse;
+mod reader;
+#[cfg(feature = "serde")]
+mod ser;
 
+#[cfg(feature = "async")]
+pub use async_writer::*;
 pub use attribute::*;
+pub use canonical::Canonicalization;
+pub use case::Case;
+pub use chunks::XMLChunks;
 pub use data::*;
 pub use declaration::*;
+pub use diff::*;
 pub use into_xml::*;
+pub use map::*;
 pub use namespace::*;
+pub use reader::*;
+#[cfg(feature = "serde")]
+pub use ser::to_xml;
 
 #[cfg(any(feature = "macro", test))]
 pub use flexml_macro as macros;
 
-use heck::{
-    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase as _, ToSnakeCase,
-    ToTrainCase, ToUpperCamelCase,
-};
-
 use std::{
     error::Error,
     fmt::{self, Debug, Display},
@@ -28,6 +44,10 @@ use std::{
 
 #[derive(Debug)]
 pub enum XMLError {
+    Case(String),
+    /// A map key that isn't a valid element or attribute name, for [`MapShape::KeyName`] and
+    /// maps of attributes.
+    Name(String),
     NamespaceNotFound(String),
     NamespaceOnText,
     Other(String),
@@ -38,6 +58,8 @@ impl Error for XMLError {}
 impl Display for XMLError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::Case(v) => write!(f, "XMLError::Case - {v}"),
+            Self::Name(v) => write!(f, "XMLError::Name - \"{v}\" is not a valid XML name"),
             Self::NamespaceNotFound(v) => write!(
                 f,
                 "XMLError::NamespaceNotFound - Namespace \"{v}\" not defined"
@@ -57,23 +79,12 @@ impl From<String> for XMLError {
     }
 }
 
-pub(crate) fn conv_case<T: Display, U: Display>(input: T, case: U) -> String {
-    let input = input.to_string();
-    match case.to_string().as_str() {
-        "KebabCase" | "kebab-kase" => input.to_kebab_case(),
-        "LowerCamelCase" | "lowerCamelCase" => input.to_lower_camel_case(),
-        "ShoutyKebabCase" | "SHOUTY-KEBAB-CASE" => input.to_shouty_kebab_case(),
-        // "snek" - What chicanery, what shenanigans - and dare I say it - what tomfoolery!
-        "ShoutySnakeCase" | "SHOUTY_SNAKE_CASE" | "ShoutySnekCase" | "SHOUTY_SNEK_CASE" => {
-            input.to_shouty_snake_case()
-        }
-        "SnakeCase" | "snake_case" | "SnekCase" | "snek_case" => input.to_snake_case(),
-
-        "TitleCase" | "Title Case" => {
-            panic!("XML does not allow the 'Title Case' casing scheme.")
-        }
-        "TrainCase" | "Train-Case" => input.to_train_case(),
-        "UpperCamelCase" | "PascalCase" => input.to_upper_camel_case(),
-        r => panic!("Unknown case '{r}'"),
-    }
+/// Whether `name` is a valid XML element name. Non-ASCII letters and digits are accepted, as
+/// they are by most of the `Name` production.
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'))
 }
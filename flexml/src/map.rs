@@ -0,0 +1,179 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    hash::BuildHasher,
+};
+
+use crate::{
+    is_xml_name, ToXMLAttributeValue, ToXMLAttributes, TryIntoXML, XMLAttribute, XMLError, XML,
+};
+
+/// How the entries of a map are rendered. Chosen with `#[map(...)]` in the derive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapShape {
+    /// `<entry key="k">v</entry>`
+    KeyAttribute {
+        entry: &'static str,
+        key: &'static str,
+    },
+    /// `<k>v</k>`. Keys that aren't valid XML names are an [`XMLError::Name`].
+    KeyName,
+    /// `<entry><key>k</key><value>v</value></entry>`
+    Nested {
+        entry: &'static str,
+        key: &'static str,
+        value: &'static str,
+    },
+}
+
+impl Default for MapShape {
+    fn default() -> Self {
+        Self::KeyAttribute {
+            entry: "entry",
+            key: "key",
+        }
+    }
+}
+
+impl MapShape {
+    /// Renders `entries` in order, as an untagged container of entry elements.
+    ///
+    /// # Errors
+    /// Returns an error if a value fails to convert, or a key isn't a valid name for
+    /// [`MapShape::KeyName`].
+    pub fn entries<'a, K, V, I>(self, entries: I) -> Result<XML, XMLError>
+    where
+        K: Display + ?Sized + 'a,
+        V: TryIntoXML + ?Sized + 'a,
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+    {
+        let mut xml = XML::new_untagged();
+        for (k, v) in entries {
+            let value = v.try_to_xml()?;
+            xml.add_datum(match self {
+                Self::KeyAttribute { entry, key } => XML::new(entry)
+                    .attribute(XMLAttribute::new(key, &k))
+                    .datum(value),
+                Self::KeyName => {
+                    let name = k.to_string();
+                    if !is_xml_name(&name) {
+                        return Err(XMLError::Name(name));
+                    }
+                    XML::new(name).datum(value)
+                }
+                Self::Nested {
+                    entry,
+                    key,
+                    value: value_name,
+                } => XML::new(entry)
+                    .datum(XML::new(key).text(k))
+                    .datum(XML::new(value_name).datum(value)),
+            });
+        }
+        Ok(xml)
+    }
+}
+
+/// Maps serialized as a list of entries, in a deterministic order.
+pub trait XMLMap {
+    type Key: Display + ?Sized;
+    type Value: TryIntoXML + ?Sized;
+
+    /// The entries in document order. Unordered maps are sorted by their rendered key.
+    fn xml_entries(&self) -> Vec<(&Self::Key, &Self::Value)>;
+
+    /// # Errors
+    /// See [`MapShape::entries`].
+    fn try_to_xml_map(&self, shape: MapShape) -> Result<XML, XMLError> {
+        shape.entries(self.xml_entries())
+    }
+}
+
+impl<K: Display, V: TryIntoXML, S: BuildHasher> XMLMap for HashMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn xml_entries(&self) -> Vec<(&K, &V)> {
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort_by_cached_key(|(k, _)| k.to_string());
+        entries
+    }
+}
+
+impl<K: Display, V: TryIntoXML> XMLMap for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn xml_entries(&self) -> Vec<(&K, &V)> {
+        self.iter().collect()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Display, V: TryIntoXML, S> XMLMap for indexmap::IndexMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn xml_entries(&self) -> Vec<(&K, &V)> {
+        self.iter().collect()
+    }
+}
+
+impl<K: Display, V: TryIntoXML, S: BuildHasher> TryIntoXML for HashMap<K, V, S> {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        self.try_to_xml_map(MapShape::default())
+    }
+}
+
+impl<K: Display, V: TryIntoXML> TryIntoXML for BTreeMap<K, V> {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        self.try_to_xml_map(MapShape::default())
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Display, V: TryIntoXML, S> TryIntoXML for indexmap::IndexMap<K, V, S> {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        self.try_to_xml_map(MapShape::default())
+    }
+}
+
+/// Attributes named after the keys of a map, e.g. an `#[any_attributes]` catch-all. Values of
+/// `None` are left out.
+fn map_attributes<'a, K, V, I>(entries: I) -> Result<Vec<XMLAttribute>, XMLError>
+where
+    K: Display + 'a,
+    V: ToXMLAttributeValue + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    let mut attributes = Vec::new();
+    for (k, v) in entries {
+        let key = k.to_string();
+        if !is_xml_name(&key) {
+            return Err(XMLError::Name(key));
+        }
+        attributes.extend(XMLAttribute::from_value(key, v));
+    }
+    Ok(attributes)
+}
+
+impl<K: Display, V: ToXMLAttributeValue, S: BuildHasher> ToXMLAttributes for HashMap<K, V, S> {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort_by_cached_key(|(k, _)| k.to_string());
+        map_attributes(entries)
+    }
+}
+
+impl<K: Display, V: ToXMLAttributeValue> ToXMLAttributes for BTreeMap<K, V> {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        map_attributes(self)
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Display, V: ToXMLAttributeValue, S> ToXMLAttributes for indexmap::IndexMap<K, V, S> {
+    fn to_xml_attributes(&self) -> Result<Vec<XMLAttribute>, XMLError> {
+        map_attributes(self)
+    }
+}
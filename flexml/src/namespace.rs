@@ -57,9 +57,37 @@ impl XMLNamespaces {
         Ok(None)
     }
 
+    /// # Errors
+    /// Will return an error if the global lock is poisoned.
+    pub fn get_by_uri(uri: &str) -> Result<Option<XMLNamespace>, XMLError> {
+        let namespaces = read_global()?;
+        Ok(namespaces.values().find(|ns| ns.uri == uri).cloned())
+    }
+
     /// # Errors
     /// Will return an error if the global lock is poisoned.
     pub fn insert(namespace: &'static str, uri: &'static str) -> Result<(), XMLError> {
+        Self::insert_aliased(namespace, None, uri)
+    }
+
+    /// Inserts a namespace under a fixed alias, like `xsi`. If another namespace already uses
+    /// `alias`, one is picked as in [`XMLNamespaces::insert`].
+    ///
+    /// # Errors
+    /// Will return an error if the global lock is poisoned.
+    pub fn insert_with_alias(
+        namespace: &'static str,
+        alias: &'static str,
+        uri: &'static str,
+    ) -> Result<(), XMLError> {
+        Self::insert_aliased(namespace, Some(alias), uri)
+    }
+
+    fn insert_aliased(
+        namespace: &'static str,
+        alias: Option<&'static str>,
+        uri: &'static str,
+    ) -> Result<(), XMLError> {
         let mut ns = write_global()?;
         let namespace = namespace.to_string();
         let uri = uri.to_string();
@@ -69,15 +97,21 @@ impl XMLNamespaces {
             return Ok(());
         }
 
-        let mut values = ns.values();
-        let mut alias = namespace.to_lowercase()[0..=0].to_string();
-        loop {
-            if values.any(|v| v.alias == alias) {
-                alias.replace_range(.., &namespace.to_lowercase()[0..=alias.len()]);
-                continue;
+        let alias = match alias {
+            Some(alias) if !ns.values().any(|v| v.alias == alias) => alias.to_string(),
+            _ => {
+                let mut values = ns.values();
+                let mut alias = namespace.to_lowercase()[0..=0].to_string();
+                loop {
+                    if values.any(|v| v.alias == alias) {
+                        alias.replace_range(.., &namespace.to_lowercase()[0..=alias.len()]);
+                        continue;
+                    }
+                    break;
+                }
+                alias
             }
-            break;
-        }
+        };
 
         let namespace = XMLNamespace {
             alias,
@@ -92,6 +126,37 @@ impl XMLNamespaces {
     }
 }
 
+/// A namespace declared once for a whole crate, so the derive can refer to it by path with
+/// `#[namespace(path::TO_DEF)]` instead of repeating `#[namespaces]` on every type.
+///
+/// ```
+/// use flexml::XMLNamespaceDef;
+///
+/// pub const SOAP: XMLNamespaceDef =
+///     XMLNamespaceDef::new("Soap", "http://schemas.xmlsoap.org/soap/envelope/");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XMLNamespaceDef {
+    pub name: &'static str,
+    pub uri: &'static str,
+}
+
+impl XMLNamespaceDef {
+    #[must_use]
+    pub const fn new(name: &'static str, uri: &'static str) -> Self {
+        Self { name, uri }
+    }
+
+    /// Inserts the namespace into [`XMLNamespaces`] if it isn't there yet, returning its name.
+    ///
+    /// # Errors
+    /// Will return an error if the global lock is poisoned.
+    pub fn register(&self) -> Result<&'static str, XMLError> {
+        XMLNamespaces::insert(self.name, self.uri)?;
+        Ok(self.name)
+    }
+}
+
 // #[derive(Debug, Clone)]
 // pub struct XMLNamespaces<'a>(&'a Lazy<RwLock<HashMap<&'static str, String>>>);
 //
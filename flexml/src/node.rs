@@ -1,18 +1,17 @@
 use log::warn;
 
 use crate::attribute::XMLAttribute;
-use crate::conv_case;
-use crate::{IntoXML, XMLError, XMLNamespace, XMLNamespaces, XML};
+use crate::{Case, IntoXML, TryIntoXML, XMLError, XMLNamespace, XMLNamespaces, XML};
 
-use std::fmt::Display;
+use std::fmt::{self, Display, Write};
 
 #[derive(Debug, Clone)]
 pub struct XMLNode {
-    name: String,
-    namespace: Option<XMLNamespace>,
+    pub(crate) name: String,
+    pub(crate) namespace: Option<XMLNamespace>,
 
-    attributes: Vec<XMLAttribute>,
-    data: Vec<XML>,
+    pub(crate) attributes: Vec<XMLAttribute>,
+    pub(crate) data: Vec<XML>,
 }
 
 impl XMLNode {
@@ -54,13 +53,14 @@ impl XMLNode {
         self.name = name.to_string();
     }
 
-    pub fn case<T: Display>(mut self, case: T) -> Self {
+    #[must_use]
+    pub fn case(mut self, case: Case) -> Self {
         self.set_case(case);
         self
     }
 
-    pub fn set_case<T: Display>(&mut self, case: T) {
-        self.name = conv_case(&self.name, case);
+    pub fn set_case(&mut self, case: Case) {
+        self.name = case.apply(&self.name);
     }
 
     #[inline]
@@ -107,14 +107,13 @@ impl XMLNode {
         self
     }
 
+    /// Adds each of `data` as a child. [`XML::None`] adds nothing, so a node whose children
+    /// are all skipped is still written as an empty element.
     #[inline]
     pub fn add_data<T: IntoXML>(&mut self, data: &[T]) {
-        self.data.extend_from_slice(
-            data.iter()
-                .map(IntoXML::to_xml)
-                .collect::<Vec<XML>>()
-                .as_slice(),
-        );
+        for datum in data {
+            self.add_datum(datum.to_xml());
+        }
     }
 
     #[allow(clippy::needless_pass_by_value)] // May or may not be a ref, generic
@@ -127,7 +126,10 @@ impl XMLNode {
     #[allow(clippy::needless_pass_by_value)] // May or may not be a ref, generic
     #[inline]
     pub fn add_datum<T: IntoXML>(&mut self, datum: T) {
-        self.data.push(datum.to_xml());
+        match datum.to_xml() {
+            XML::None => {}
+            datum => self.data.push(datum),
+        }
     }
 
     #[inline]
@@ -153,31 +155,18 @@ impl XMLNode {
     }
 
     #[inline]
-    pub fn text<T: Display>(mut self, text: &T) -> Self {
+    pub fn text<T: Display + ?Sized>(mut self, text: &T) -> Self {
         self.add_datum(text.to_string().to_xml());
         self
     }
 
     #[inline]
-    pub fn add_text<T: Display>(&mut self, text: &T) {
+    pub fn add_text<T: Display + ?Sized>(&mut self, text: &T) {
         self.add_datum(text.to_string().to_xml());
     }
 
     pub fn sub_fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let ns_tag = self.namespace.as_ref().map_or_else(
-            || self.name.clone(),
-            |ns| format!("{}:{}", ns.alias, self.name),
-        );
-
-        write!(f, "<{ns_tag}")?;
-
-        for attribute in &self.attributes {
-            let ns_tag = attribute.namespace.as_ref().map_or_else(
-                || attribute.key.clone(),
-                |ns| format!("{}:{}", ns.alias, attribute.key),
-            );
-            write!(f, r#" {ns_tag}="{}""#, attribute.value)?;
-        }
+        self.write_start_tag(f, &[])?;
 
         if self.data.is_empty() {
             write!(f, "/>")
@@ -186,42 +175,52 @@ impl XMLNode {
             for datum in &self.data {
                 datum.sub_fmt(f)?;
             }
-            write!(f, "</{ns_tag}>")?;
-            Ok(())
+            self.write_end_tag(f)
         }
     }
-}
 
-impl IntoXML for XMLNode {
-    fn to_xml(&self) -> XML {
-        XML::Node(self.to_owned())
-    }
-}
-
-impl Display for XMLNode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let ns_tag = self.namespace.as_ref().map_or_else(
+    pub(crate) fn ns_tag(&self) -> String {
+        self.namespace.as_ref().map_or_else(
             || self.name.clone(),
             |ns| format!("{}:{}", ns.alias, self.name),
-        );
+        )
+    }
 
-        write!(f, "<{ns_tag}")?;
+    /// Writes the start tag with its attributes and the given namespace declarations, leaving
+    /// it unterminated so the caller can decide between `>` and `/>`.
+    pub(crate) fn write_start_tag<W: Write>(
+        &self,
+        w: &mut W,
+        namespaces: &[XMLNamespace],
+    ) -> fmt::Result {
+        write!(w, "<{}", self.ns_tag())?;
 
         for attribute in &self.attributes {
-            let ns_tag = attribute.namespace.as_ref().map_or_else(
-                || attribute.key.clone(),
-                |ns| format!("{}:{}", ns.alias, attribute.key),
-            );
-            write!(f, r#" {ns_tag}="{}""#, attribute.value)?;
+            write!(w, r#" {}="{}""#, attribute.ns_tag(), attribute.value)?;
         }
 
-        let namespaces = self.namespaces();
-
         for namespace in namespaces {
             if let Ok(Some(namespace)) = XMLNamespaces::get(&namespace.name) {
-                write!(f, r#" xmlns:{}="{}""#, namespace.alias, namespace.uri)?;
+                write!(w, r#" xmlns:{}="{}""#, namespace.alias, namespace.uri)?;
             }
         }
+        Ok(())
+    }
+
+    pub(crate) fn write_end_tag<W: Write>(&self, w: &mut W) -> fmt::Result {
+        write!(w, "</{}>", self.ns_tag())
+    }
+}
+
+impl TryIntoXML for XMLNode {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Ok(XML::Node(self.to_owned()))
+    }
+}
+
+impl Display for XMLNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_start_tag(f, &self.namespaces())?;
 
         if self.data.is_empty() {
             write!(f, "/>")
@@ -230,8 +229,7 @@ impl Display for XMLNode {
             for datum in &self.data {
                 datum.sub_fmt(f)?;
             }
-            write!(f, "</{ns_tag}>")?;
-            Ok(())
+            self.write_end_tag(f)
         }
     }
 }
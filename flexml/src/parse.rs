@@ -0,0 +1,341 @@
+use crate::node::XMLNode;
+use crate::{
+    XMLAttribute, XMLDeclaration, XMLEncoding, XMLError, XMLNamespace, XMLNamespaces, XML,
+};
+
+/// Parses a document into an [`XML`] tree, so markup passed to [`crate::assert_xml_eq`] can be
+/// compared against generated trees. This isn't a general purpose reader and isn't exposed.
+///
+/// Prefixes are resolved through the `xmlns` declarations in scope. Namespaces whose URI is
+/// registered in [`XMLNamespaces`] resolve to that namespace, others are kept under their prefix
+/// without being registered. Comments, processing instructions and doctypes are skipped, and
+/// CDATA sections become text.
+pub fn parse(s: &str) -> Result<XML, XMLError> {
+    Parser {
+        input: s,
+        position: 0,
+        scopes: Vec::new(),
+    }
+    .document()
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// `(prefix, uri)` pairs declared by each open element.
+    scopes: Vec<Vec<(String, String)>>,
+}
+
+impl<'a> Parser<'a> {
+    fn document(mut self) -> Result<XML, XMLError> {
+        self.eat("\u{feff}");
+
+        let declaration = if self.eat("<?xml ") {
+            Some(self.declaration()?)
+        } else {
+            None
+        };
+
+        self.misc()?;
+        let node = self.element()?;
+        self.misc()?;
+
+        if self.position < self.input.len() {
+            return Err(self.error("Unexpected content after the root element"));
+        }
+
+        Ok(match declaration {
+            Some(declaration) => XML::Declaration(declaration, node),
+            None => XML::Node(node),
+        })
+    }
+
+    fn declaration(&mut self) -> Result<XMLDeclaration, XMLError> {
+        let mut declaration = XMLDeclaration::default();
+        loop {
+            self.whitespace();
+            if self.eat("?>") {
+                return Ok(declaration);
+            }
+
+            let (name, value) = self.attribute()?;
+            match name.as_str() {
+                "version" => {
+                    let (major, minor) = value.split_once('.').unwrap_or((&value, "0"));
+                    match (major.parse(), minor.parse()) {
+                        (Ok(major), Ok(minor)) => declaration.set_version((major, minor)),
+                        _ => return Err(self.error(format!("Invalid version \"{value}\""))),
+                    }
+                }
+                "encoding" if value.eq_ignore_ascii_case("utf-8") => {
+                    declaration.set_encoding(XMLEncoding::UTF8);
+                }
+                "encoding" => {
+                    return Err(self.error(format!("Unsupported encoding \"{value}\"")));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips whitespace, comments, processing instructions and doctypes.
+    fn misc(&mut self) -> Result<(), XMLError> {
+        loop {
+            self.whitespace();
+            if !self.skip_markup()? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Skips a comment, processing instruction or doctype, returning whether one was found.
+    fn skip_markup(&mut self) -> Result<bool, XMLError> {
+        let end = if self.eat("<!--") {
+            "-->"
+        } else if self.eat("<?") {
+            "?>"
+        } else if self.eat("<!DOCTYPE") {
+            ">"
+        } else {
+            return Ok(false);
+        };
+
+        self.take_until(end)?;
+        Ok(true)
+    }
+
+    fn element(&mut self) -> Result<XMLNode, XMLError> {
+        if !self.eat("<") {
+            return Err(self.error("Expected an element"));
+        }
+        let tag = self.name()?;
+
+        let mut attributes = Vec::new();
+        let mut scope = Vec::new();
+        let empty = loop {
+            self.whitespace();
+            if self.eat("/>") {
+                break true;
+            }
+            if self.eat(">") {
+                break false;
+            }
+
+            let (name, value) = self.attribute()?;
+            if name == "xmlns" {
+                scope.push((String::new(), value));
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                scope.push((prefix.to_string(), value));
+            } else {
+                attributes.push((name, value));
+            }
+        };
+        self.scopes.push(scope);
+
+        let (prefix, name) = split_name(&tag);
+        let mut node = XMLNode::new(name);
+        node.namespace = self.resolve(prefix.unwrap_or_default())?;
+
+        for (key, value) in attributes {
+            let (prefix, key) = split_name(&key);
+            let mut attribute = XMLAttribute::new(key, &value);
+            if let Some(prefix) = prefix {
+                attribute.namespace = self.resolve(prefix)?;
+            }
+            node.add_attribute(attribute);
+        }
+
+        if !empty {
+            self.content(&mut node)?;
+            if !self.eat(&tag) {
+                return Err(self.error(format!("Expected closing tag for <{tag}>")));
+            }
+            self.whitespace();
+            if !self.eat(">") {
+                return Err(self.error("Expected '>'"));
+            }
+        }
+
+        self.scopes.pop();
+        Ok(node)
+    }
+
+    /// Parses child content up to and including the `</` of the closing tag.
+    fn content(&mut self, node: &mut XMLNode) -> Result<(), XMLError> {
+        let mut text = String::new();
+        loop {
+            if self.eat("</") {
+                break;
+            }
+            if self.eat("<![CDATA[") {
+                text.push_str(self.take_until("]]>")?);
+                continue;
+            }
+            if self.skip_markup()? {
+                continue;
+            }
+            if self.rest().starts_with('<') {
+                if !text.is_empty() {
+                    node.data.push(XML::Text(std::mem::take(&mut text)));
+                }
+                let child = self.element()?;
+                node.data.push(XML::Node(child));
+                continue;
+            }
+
+            let end = self.rest().find('<').unwrap_or_else(|| self.rest().len());
+            if end == 0 {
+                return Err(self.error("Unexpected end of input"));
+            }
+            let raw = &self.input[self.position..self.position + end];
+            text.push_str(&self.unescape(raw)?);
+            self.position += end;
+        }
+
+        if !text.is_empty() {
+            node.data.push(XML::Text(text));
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self) -> Result<(String, String), XMLError> {
+        let name = self.name()?;
+        self.whitespace();
+        if !self.eat("=") {
+            return Err(self.error(format!("Expected '=' after attribute {name}")));
+        }
+        self.whitespace();
+
+        let quote = if self.eat("\"") {
+            "\""
+        } else if self.eat("'") {
+            "'"
+        } else {
+            return Err(self.error("Expected a quoted attribute value"));
+        };
+        let raw = self.take_until(quote)?;
+        let value = self.unescape(raw)?;
+
+        Ok((name, value))
+    }
+
+    fn name(&mut self) -> Result<String, XMLError> {
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or_else(|| self.rest().len());
+        if len == 0 {
+            return Err(self.error("Expected a name"));
+        }
+
+        let name = self.rest()[..len].to_string();
+        self.position += len;
+        Ok(name)
+    }
+
+    fn resolve(&self, prefix: &str) -> Result<Option<XMLNamespace>, XMLError> {
+        let Some(uri) = self
+            .scopes
+            .iter()
+            .rev()
+            .flatten()
+            .find_map(|(p, uri)| (p == prefix).then_some(uri))
+        else {
+            if prefix.is_empty() {
+                return Ok(None);
+            }
+            return Err(self.error(format!("Undeclared namespace prefix \"{prefix}\"")));
+        };
+
+        if uri.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(XMLNamespaces::get_by_uri(uri)?.unwrap_or_else(|| {
+            // Default namespaces still need a prefix to be written back out.
+            let alias = if prefix.is_empty() { "ns" } else { prefix };
+            XMLNamespace {
+                alias: alias.to_string(),
+                name: alias.to_string(),
+                uri: uri.clone(),
+            }
+        })))
+    }
+
+    fn unescape(&self, raw: &str) -> Result<String, XMLError> {
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(start) = rest.find('&') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+
+            let Some(end) = rest.find(';') else {
+                return Err(self.error("Unterminated entity reference"));
+            };
+            let entity = &rest[..end];
+            rest = &rest[end + 1..];
+
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map_or_else(
+                        || entity.strip_prefix('#').and_then(|n| n.parse().ok()),
+                        |hex| u32::from_str_radix(hex, 16).ok(),
+                    )
+                    .and_then(char::from_u32),
+            };
+            match c {
+                Some(c) => out.push(c),
+                None => return Err(self.error(format!("Unknown entity \"&{entity};\""))),
+            }
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.position..]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Returns everything up to `end`, moving past it.
+    fn take_until(&mut self, end: &str) -> Result<&'a str, XMLError> {
+        let input = self.input;
+        let Some(len) = self.rest().find(end) else {
+            return Err(self.error(format!("Expected \"{end}\"")));
+        };
+        let taken = &input[self.position..self.position + len];
+        self.position += len + end.len();
+        Ok(taken)
+    }
+
+    fn error<T: std::fmt::Display>(&self, message: T) -> XMLError {
+        XMLError::Other(format!(
+            "Could not parse XML - {message} at byte {}",
+            self.position
+        ))
+    }
+}
+
+fn split_name(name: &str) -> (Option<&str>, &str) {
+    name.split_once(':')
+        .map_or((None, name), |(prefix, name)| (Some(prefix), name))
+}
@@ -0,0 +1,46 @@
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+use crate::XMLChunks;
+
+/// An [`io::Read`] adapter that serializes an [`XML`](crate::XML) tree as bytes are pulled from
+/// it.
+///
+/// The tree itself is already in memory, but its output is produced one fragment at a time, so
+/// it can be handed to anything that consumes a reader (`std::io::copy`, compressors, HTTP
+/// clients) without formatting the complete `String` first.
+#[derive(Debug)]
+pub struct XMLReader<'a> {
+    chunks: XMLChunks<'a>,
+    current: Cow<'a, str>,
+    position: usize,
+}
+
+impl<'a> From<XMLChunks<'a>> for XMLReader<'a> {
+    fn from(chunks: XMLChunks<'a>) -> Self {
+        Self {
+            chunks,
+            current: Cow::Borrowed(""),
+            position: 0,
+        }
+    }
+}
+
+impl Read for XMLReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.current.len() {
+            let Some(chunk) = self.chunks.next() else {
+                return Ok(0);
+            };
+            self.current = chunk;
+            self.position = 0;
+        }
+
+        let remaining = &self.current.as_bytes()[self.position..];
+        let len = remaining.len().min(buf.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
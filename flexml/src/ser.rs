@@ -0,0 +1,699 @@
+use std::fmt::Display;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::node::XMLNode;
+use crate::{is_xml_name, XMLAttribute, XMLError, XMLNamespace, XMLNamespaces, XML};
+
+/// Serializes any [`Serialize`] value into an [`XML`] tree.
+///
+/// Conventions:
+/// - Structs become elements named after the struct. As a field value, the element takes the
+///   field's name instead.
+/// - Fields become child elements, in order. Sequences repeat the element once per item, and
+///   `None` fields are left out.
+/// - Fields prefixed with `@` become attributes. Unit variants are rendered as their name, and
+///   sequences of scalars as a space-separated list.
+/// - A `$value` field is inserted as content without an element of its own, which is how text
+///   content is added next to attributes.
+/// - Enum variants become elements named after the variant. As a field value, the variant's
+///   element is nested inside the field's element.
+/// - Tuple variants repeat the variant's element once per field.
+/// - Newtype structs are transparent, and maps use their keys as element names. Keys are taken
+///   as they are: the `@`, `$value` and namespace conventions only apply to struct fields,
+///   including fields brought in with `#[serde(flatten)]`.
+/// - A field name of the form `Namespace:name` (through `#[serde(rename = "...")]`) places the
+///   element or attribute in that namespace, which must already be inserted into
+///   [`XMLNamespaces`].
+///
+/// ```
+/// use flexml::assert_xml_eq;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Price {
+///     #[serde(rename = "@currency")]
+///     currency: String,
+///     #[serde(rename = "$value")]
+///     amount: f64,
+/// }
+///
+/// let price = Price { currency: "EUR".into(), amount: 12.5 };
+/// assert_xml_eq!(
+///     r#"<Price currency="EUR">12.5</Price>"#,
+///     flexml::to_xml(&price).expect("Failed to serialize")
+/// );
+/// ```
+///
+/// # Errors
+/// Returns an error if a namespace isn't defined, if an attribute's value isn't a scalar, if a
+/// map key isn't a string or a valid XML name, or if the value's `Serialize` implementation
+/// fails.
+pub fn to_xml<T: Serialize + ?Sized>(value: &T) -> Result<XML, XMLError> {
+    value.serialize(XMLSerializer).map(Content::into_xml)
+}
+
+impl ser::Error for XMLError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Other(msg.to_string())
+    }
+}
+
+/// The serialized form of a value, before its place in the parent is known.
+enum Content {
+    Text(String),
+    /// A struct or map, whose element takes the name of the field it's stored in.
+    Element(XMLNode),
+    /// An enum variant, whose element is kept inside the field it's stored in.
+    Variant(XMLNode),
+    Seq(Vec<Self>),
+    None,
+}
+
+impl Content {
+    fn into_xml(self) -> XML {
+        match self {
+            Self::Text(text) => XML::Text(text),
+            Self::Element(node) | Self::Variant(node) => XML::Node(node),
+            Self::Seq(items) => XML::Container(items.into_iter().map(Self::into_xml).collect()),
+            Self::None => XML::None,
+        }
+    }
+}
+
+/// Splits a `Namespace:name` into its name and registered namespace.
+fn qualify(name: &str) -> Result<(&str, Option<XMLNamespace>), XMLError> {
+    let Some((namespace, name)) = name.split_once(':') else {
+        return Ok((name, None));
+    };
+
+    XMLNamespaces::get(&namespace.to_string())?.map_or_else(
+        || Err(XMLError::NamespaceNotFound(namespace.to_string())),
+        |ns| Ok((name, Some(ns))),
+    )
+}
+
+fn element(name: &str) -> Result<XMLNode, XMLError> {
+    let (name, namespace) = qualify(name)?;
+    let mut node = XMLNode::new(name);
+    node.namespace = namespace;
+    Ok(node)
+}
+
+/// Places a field's value in `node` following the conventions of [`to_xml`].
+fn add_field(node: &mut XMLNode, key: &str, value: Content) -> Result<(), XMLError> {
+    if let Some(key) = key.strip_prefix('@') {
+        let value = match value {
+            Content::None => return Ok(()),
+            Content::Text(text) => text,
+            Content::Variant(variant) if is_unit(&variant) => variant.name,
+            Content::Seq(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Content::Text(text) => Ok(text),
+                    Content::Variant(variant) if is_unit(&variant) => Ok(variant.name),
+                    _ => Err(XMLError::Other(format!(
+                        "Attribute {key} may only hold a sequence of scalars"
+                    ))),
+                })
+                .collect::<Result<Vec<String>, XMLError>>()?
+                .join(" "),
+            Content::Element(_) | Content::Variant(_) => {
+                return Err(XMLError::Other(format!(
+                    "Attribute {key} may only hold a scalar"
+                )))
+            }
+        };
+
+        let (key, namespace) = qualify(key)?;
+        let mut attribute = XMLAttribute::new(key, &value);
+        attribute.namespace = namespace;
+        node.add_attribute(attribute);
+        return Ok(());
+    }
+
+    if key == "$value" {
+        node.add_datum(value.into_xml());
+        return Ok(());
+    }
+
+    let (name, namespace) = qualify(key)?;
+    add_child(node, name, namespace.as_ref(), value);
+    Ok(())
+}
+
+/// Places a map entry's value in `node`. Keys are used as element names as they are, so they
+/// don't get the field conventions of [`to_xml`].
+fn add_entry(node: &mut XMLNode, key: &str, value: Content) -> Result<(), XMLError> {
+    if !is_xml_name(key) {
+        return Err(XMLError::Name(key.to_string()));
+    }
+
+    add_child(node, key, None, value);
+    Ok(())
+}
+
+/// Whether `variant` is a unit variant's element, which attributes hold as the variant's name.
+const fn is_unit(variant: &XMLNode) -> bool {
+    variant.attributes.is_empty() && variant.data.is_empty()
+}
+
+fn add_child(node: &mut XMLNode, name: &str, namespace: Option<&XMLNamespace>, value: Content) {
+    let named = || {
+        let mut element = XMLNode::new(name);
+        element.namespace = namespace.cloned();
+        element
+    };
+
+    match value {
+        Content::None => {}
+        Content::Text(text) => node.add_node(named().text(&text)),
+        Content::Element(mut child) => {
+            child.set_name(name);
+            if namespace.is_some() {
+                child.namespace = namespace.cloned();
+            }
+            node.add_node(child);
+        }
+        Content::Variant(child) => node.add_node(named().node(child)),
+        Content::Seq(items) => {
+            for item in items {
+                add_child(node, name, namespace, item);
+            }
+        }
+    }
+}
+
+struct XMLSerializer;
+
+impl ser::Serializer for XMLSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = StructSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Content, XMLError> {
+        Ok(Content::Text(v.to_string()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Content, XMLError> {
+        Err(XMLError::Other(
+            "Raw bytes can't be serialized, encode them as a string first".to_string(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Content, XMLError> {
+        Ok(Content::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Content, XMLError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Content, XMLError> {
+        Ok(Content::None)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Content, XMLError> {
+        element(name).map(Content::Element)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Content, XMLError> {
+        element(variant).map(Content::Variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Content, XMLError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Content, XMLError> {
+        let mut node = element(variant)?;
+        match value.serialize(self)? {
+            // A struct takes the variant's place, keeping its own namespace unless the variant
+            // sets one.
+            Content::Element(mut inner) => {
+                inner.name = node.name;
+                if node.namespace.is_some() {
+                    inner.namespace = node.namespace;
+                }
+                node = inner;
+            }
+            content => add_field(&mut node, "$value", content)?,
+        }
+        Ok(Content::Variant(node))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, XMLError> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, XMLError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, XMLError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSeqSerializer, XMLError> {
+        Ok(VariantSeqSerializer {
+            variant: element(variant)?,
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, XMLError> {
+        Ok(MapSerializer {
+            node: XMLNode::new("map"),
+            key: None,
+            // Structs with `#[serde(flatten)]` fields are maps of unknown length.
+            fields: len.is_none(),
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<StructSerializer, XMLError> {
+        Ok(StructSerializer {
+            node: element(name)?,
+            variant: false,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<StructSerializer, XMLError> {
+        Ok(StructSerializer {
+            node: element(variant)?,
+            variant: true,
+        })
+    }
+}
+
+struct SeqSerializer {
+    items: Vec<Content>,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), XMLError> {
+        self.items.push(value.serialize(XMLSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        Ok(Content::Seq(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), XMLError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), XMLError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Repeats the variant's element once per field, the way sequences repeat a field's element.
+struct VariantSeqSerializer {
+    variant: XMLNode,
+    items: Vec<Content>,
+}
+
+impl ser::SerializeTupleVariant for VariantSeqSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), XMLError> {
+        let mut node = self.variant.clone();
+        add_field(&mut node, "$value", value.serialize(XMLSerializer)?)?;
+        self.items.push(Content::Variant(node));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        Ok(Content::Seq(self.items))
+    }
+}
+
+struct MapSerializer {
+    node: XMLNode,
+    key: Option<String>,
+    /// Whether keys are field names, following the field conventions of [`to_xml`].
+    fields: bool,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), XMLError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), XMLError> {
+        let Some(key) = self.key.take() else {
+            return Err(XMLError::Other(
+                "Map value serialized before its key".to_string(),
+            ));
+        };
+        let value = value.serialize(XMLSerializer)?;
+        if self.fields {
+            add_field(&mut self.node, &key, value)
+        } else {
+            add_entry(&mut self.node, &key, value)
+        }
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        Ok(Content::Element(self.node))
+    }
+}
+
+struct StructSerializer {
+    node: XMLNode,
+    variant: bool,
+}
+
+impl ser::SerializeStruct for StructSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), XMLError> {
+        add_field(&mut self.node, key, value.serialize(XMLSerializer)?)
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        Ok(if self.variant {
+            Content::Variant(self.node)
+        } else {
+            Content::Element(self.node)
+        })
+    }
+}
+
+impl ser::SerializeStructVariant for StructSerializer {
+    type Ok = Content;
+    type Error = XMLError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), XMLError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Content, XMLError> {
+        ser::SerializeStruct::end(self)
+    }
+}
+
+/// Serializes map keys, which have to become element or attribute names.
+struct KeySerializer;
+
+impl KeySerializer {
+    fn unsupported() -> XMLError {
+        XMLError::Other("Map keys must be strings, numbers or unit variants".to_string())
+    }
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = XMLError;
+
+    type SerializeSeq = Impossible<String, XMLError>;
+    type SerializeTuple = Impossible<String, XMLError>;
+    type SerializeTupleStruct = Impossible<String, XMLError>;
+    type SerializeTupleVariant = Impossible<String, XMLError>;
+    type SerializeMap = Impossible<String, XMLError>;
+    type SerializeStruct = Impossible<String, XMLError>;
+    type SerializeStructVariant = Impossible<String, XMLError>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, XMLError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_none(self) -> Result<String, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, XMLError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, XMLError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, XMLError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, XMLError> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, XMLError> {
+        Err(Self::unsupported())
+    }
+}
@@ -1,105 +1,712 @@
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase, ToTrainCase,
+    ToUpperCamelCase,
+};
 use proc_macro2::TokenStream;
 use quote::{quote, TokenStreamExt};
-use syn::{parse::Parse, punctuated::Punctuated, Attribute, Ident, Lit, LitStr, Token};
+use syn::{
+    parse::Parse, parse_quote, punctuated::Punctuated, Attribute, Expr, ExprPath, Ident, Lit,
+    LitStr, Meta, Path, Token, Type, WherePredicate,
+};
 
-use crate::NamespaceTuple;
+use crate::{type_is_option, NamespaceTuple};
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)] // One flag per marker attribute
 pub struct DeriveAttributes {
+    pub any: bool,
+    pub any_attributes: bool,
     pub attribute: bool,
-    pub case: Option<String>,
-    pub case_all: Option<String>,
+    pub attributes: bool,
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    pub case: Option<CaseArg>,
+    pub case_all: Option<CaseArg>,
     pub alias: Option<String>,
-    pub namespace: Option<String>,
+    pub namespace: Option<NamespaceRef>,
     pub namespaces: Vec<NamespaceTuple>,
     pub declaration: Option<DeclarationFormats>,
-    pub with: Option<Ident>,
+    pub flatten: bool,
+    pub into: Option<Type>,
+    pub with: Option<WithArg>,
     pub unit_repr: Option<Lit>,
+    pub text: bool,
     pub unserialized: bool,
     pub untagged: bool,
+    pub skip: SkipRules,
+    pub wrap: Option<WrapArgs>,
+    pub item_name: Option<String>,
+    pub sequence: bool,
+    pub map: Option<MapArgs>,
+    pub tag_attribute: Option<TagAttribute>,
 }
 
-#[allow(clippy::fallible_impl_from)] // Panics in macros show as editor errors
-impl From<&Vec<Attribute>> for DeriveAttributes {
-    fn from(attrs: &Vec<Attribute>) -> Self {
+/// Where a set of attributes was found, which decides the attributes allowed there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributePosition {
+    Container,
+    Field,
+    Variant,
+}
+
+impl AttributePosition {
+    const fn allows(self, id: &str) -> bool {
+        match id.as_bytes() {
+            b"bound" | b"declaration" | b"into" | b"namespaces" | b"tag_attribute"
+            | b"untagged" => matches!(self, Self::Container),
+            b"any" | b"any_attributes" | b"attribute" | b"attributes" | b"flatten"
+            | b"item_name" | b"map" | b"sequence" | b"skip_if" | b"unserialized" | b"wrap" => {
+                matches!(self, Self::Field)
+            }
+            b"skip_default" | b"skip_none" | b"text" => !matches!(self, Self::Variant),
+            b"case_all" | b"unit_repr" => !matches!(self, Self::Field),
+            _ => true,
+        }
+    }
+
+    const fn describe(self) -> &'static str {
+        match self {
+            Self::Container => "container types",
+            Self::Field => "fields",
+            Self::Variant => "enum variants",
+        }
+    }
+}
+
+impl DeriveAttributes {
+    /// Parses the attributes this derive understands, reporting malformed or misplaced ones to
+    /// `errors`.
+    pub fn parse(attrs: &[Attribute], position: AttributePosition, errors: &mut Errors) -> Self {
         let mut ret = Self::default();
+        let mut seen = Vec::new();
 
         for attr in attrs {
-            let id = attr
-                .path()
-                .get_ident()
-                .map(std::string::ToString::to_string);
-            if let Some(id) = id {
-                match id.as_str() {
-                    "attribute" => ret.attribute = true,
-                    "case" => {
-                        match attr.parse_args::<LitStr>() {
-                            Ok(s) => {
-                                ret.case = Some(s.value());
-                            }
-                            Err(e) => {
-                                panic!("Could not parse #[case] argument, expected string literal - {e}")
+            let Some(id) = attr.path().get_ident().map(ToString::to_string) else {
+                continue;
+            };
+            if !KNOWN_ATTRIBUTES.contains(&id.as_str()) {
+                continue;
+            }
+            if !position.allows(&id) {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    format!("#[{id}] is not supported on {}", position.describe()),
+                ));
+                continue;
+            }
+
+            if let Err(e) = ret.parse_attribute(&id, attr) {
+                errors.push(e);
+            }
+            seen.push((id, attr));
+        }
+
+        for (id, conflicts) in CONFLICTS {
+            if !seen.iter().any(|(seen_id, _)| seen_id == id) {
+                continue;
+            }
+            for (seen_id, attr) in &seen {
+                if conflicts.contains(&seen_id.as_str()) {
+                    errors.push(syn::Error::new_spanned(
+                        attr,
+                        format!("#[{seen_id}] cannot be combined with #[{id}]"),
+                    ));
+                }
+            }
+        }
+
+        ret
+    }
+
+    fn parse_attribute(&mut self, id: &str, attr: &Attribute) -> syn::Result<()> {
+        let has_args = !matches!(attr.meta, Meta::Path(_));
+        match id {
+            // `#[attribute("case", with = f)]` is shorthand for
+            // `#[attribute] #[case("case")] #[with(f)]`, and either argument may be left out.
+            // `display` formats the value with `ToString`, for types that can't implement
+            // `ToXMLAttributeValue`.
+            "attribute" => {
+                self.attribute = true;
+                if has_args {
+                    let args: Punctuated<AttributeArg, Token![,]> =
+                        attr.parse_args_with(Punctuated::parse_terminated)?;
+                    for arg in args {
+                        match arg {
+                            AttributeArg::Case(case) => self.case = Some(case),
+                            AttributeArg::With(with) => self.with = Some(with),
+                            AttributeArg::Display => {
+                                self.with = Some(WithArg::Function(parse_quote! {
+                                    ::std::string::ToString::to_string
+                                }));
                             }
                         }
                     }
-                    "case_all" => match attr.parse_args::<LitStr>() {
-                        Ok(s) => {
-                            ret.case_all = Some(s.value());
-                        }
-                        Err(e) => {
-                            panic!(
-                                    "Could not parse #[case_all] argument, expected string literal - {e}"
-                                )
-                        }
-                    },
-                    "name" => {
-                        ret.alias = Some(
-                            attr.parse_args::<LitStr>()
-                                .expect("Expected string literal in namespace attribute")
-                                .value(),
-                        );
-                    }
-                    "namespace" => {
-                        ret.namespace = Some(
-                            attr.parse_args::<LitStr>()
-                                .expect("Expected string literal in namespace attribute")
-                                .value(),
-                        );
-                    }
-                    "namespaces" => {
-                        let namespaces: Punctuated<NamespaceTuple, Token![,]> = attr
-                            .parse_args_with(Punctuated::parse_terminated)
-                            .expect("Failed to parse namespaces attribute");
+                }
+            }
+            "any" => self.any = true,
+            "any_attributes" => self.any_attributes = true,
+            "attributes" => self.attributes = true,
+            "bound" => self.bound = Some(attr.parse_args_with(Punctuated::parse_terminated)?),
+            "case" => self.case = Some(attr.parse_args()?),
+            "case_all" => self.case_all = Some(attr.parse_args()?),
+            "name" => self.alias = Some(attr.parse_args::<LitStr>()?.value()),
+            "namespace" => self.namespace = Some(attr.parse_args()?),
+            "namespaces" => {
+                let namespaces: Punctuated<NamespaceTuple, Token![,]> =
+                    attr.parse_args_with(Punctuated::parse_terminated)?;
+                self.namespaces.extend(namespaces);
+            }
+            "declaration" => {
+                self.declaration = Some(if has_args {
+                    attr.parse_args::<DeclarationFormats>()?
+                } else {
+                    DeclarationFormats::Empty
+                });
+            }
+            "into" => self.into = Some(attr.parse_args()?),
+            "item_name" => self.item_name = Some(attr.parse_args::<LitStr>()?.value()),
+            "with" => self.with = Some(attr.parse_args()?),
+            "unit_repr" => self.unit_repr = Some(attr.parse_args::<Lit>()?),
+            "flatten" => self.flatten = true,
+            "text" => self.text = true,
+            "map" => {
+                self.map = Some(if has_args {
+                    attr.parse_args()?
+                } else {
+                    MapArgs::default()
+                });
+            }
+            "sequence" => self.sequence = true,
+            "tag_attribute" => self.tag_attribute = Some(attr.parse_args()?),
+            "skip_if" => self.skip.skip_if = Some(attr.parse_args()?),
+            "skip_default" => self.skip.default = true,
+            "skip_none" => self.skip.none = true,
+            "unserialized" => self.unserialized = true,
+            "untagged" => self.untagged = true,
+            "wrap" => {
+                self.wrap = Some(if has_args {
+                    attr.parse_args()?
+                } else {
+                    WrapArgs::default()
+                });
+            }
+            _ => {}
+        }
 
-                        ret.namespaces.extend(namespaces);
-                    }
-                    "declaration" => {
-                        ret.declaration = Some(
-                            attr.parse_args::<DeclarationFormats>()
-                                .unwrap_or(DeclarationFormats::Empty),
-                        );
-                    }
-                    "with" => {
-                        ret.with = Some(
-                            attr.parse_args::<Ident>()
-                                .expect("Expected identifier in with attribute"),
-                        );
-                    }
-                    "unit_repr" => {
-                        ret.unit_repr =
-                            Some(attr.parse_args::<Lit>().expect("Expected literal value"));
-                    }
-                    "unserialized" => {
-                        ret.unserialized = true;
-                        break; // Don't really need to continue processing in this case.
+        if has_args
+            && matches!(
+                id,
+                "any"
+                    | "any_attributes"
+                    | "attributes"
+                    | "flatten"
+                    | "sequence"
+                    | "skip_default"
+                    | "skip_none"
+                    | "text"
+                    | "unserialized"
+                    | "untagged"
+            )
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("#[{id}] takes no arguments"),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The arguments of `#[wrap("Name", namespace = "Ns", skip_empty)]`, all optional.
+#[derive(Debug, Default)]
+pub struct WrapArgs {
+    /// The wrapper's name. Defaults to the field name, in the field's case.
+    pub name: Option<LitStr>,
+    pub namespace: Option<NamespaceRef>,
+    /// Leave the wrapper out when it would be empty, instead of writing `<Name/>`.
+    pub skip_empty: bool,
+}
+
+impl Parse for WrapArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = Self::default();
+        while !input.is_empty() {
+            if input.peek(LitStr) {
+                args.name = Some(input.parse()?);
+            } else {
+                let id: Ident = input.parse()?;
+                if id == "namespace" {
+                    let _eq: Token![=] = input.parse()?;
+                    args.namespace = Some(input.parse()?);
+                } else if id == "skip_empty" {
+                    args.skip_empty = true;
+                } else {
+                    return Err(syn::Error::new_spanned(
+                        id,
+                        "expected a name, `namespace = ...` or `skip_empty`",
+                    ));
+                }
+            }
+            if !input.is_empty() {
+                let _comma: Token![,] = input.parse()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// The attribute naming the variant of an internally tagged enum: `#[tag_attribute("type")]`, or
+/// `#[tag_attribute(xsi)]` for `xsi:type`.
+#[derive(Debug)]
+pub enum TagAttribute {
+    Name(LitStr),
+    Xsi(Ident),
+}
+
+impl TagAttribute {
+    /// The builder call adding the attribute, with `value` naming the variant.
+    pub fn to_attribute_tokens(&self, value: &TokenStream) -> TokenStream {
+        match self {
+            Self::Name(name) => quote! { .attribute(flexml::XMLAttribute::new(#name, &#value)) },
+            Self::Xsi(_) => quote! { .attribute(flexml::XMLAttribute::xsi_type(#value)?) },
+        }
+    }
+
+    pub fn span(&self) -> proc_macro2::Span {
+        match self {
+            Self::Name(name) => name.span(),
+            Self::Xsi(xsi) => xsi.span(),
+        }
+    }
+}
+
+impl Parse for TagAttribute {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return input.parse().map(Self::Name);
+        }
+        let id: Ident = input.parse()?;
+        if id != "xsi" {
+            return Err(syn::Error::new_spanned(
+                id,
+                "expected an attribute name or `xsi`",
+            ));
+        }
+        Ok(Self::Xsi(id))
+    }
+}
+
+/// The arguments of `#[map(...)]`: a shape of `key_attribute` (the default), `key_name` or
+/// `nested`, and `entry`, `key` and `value` names where the shape has them.
+#[derive(Debug, Default)]
+pub struct MapArgs {
+    shape: Option<Ident>,
+    entry: Option<LitStr>,
+    key: Option<LitStr>,
+    value: Option<LitStr>,
+}
+
+impl MapArgs {
+    /// A `flexml::MapShape` expression.
+    pub fn to_shape_tokens(&self) -> TokenStream {
+        let entry = self
+            .entry
+            .as_ref()
+            .map_or_else(|| "entry".to_string(), LitStr::value);
+        let key = self
+            .key
+            .as_ref()
+            .map_or_else(|| "key".to_string(), LitStr::value);
+        match self.shape.as_ref().map(ToString::to_string).as_deref() {
+            Some("key_name") => quote! { flexml::MapShape::KeyName },
+            Some("nested") => {
+                let value = self
+                    .value
+                    .as_ref()
+                    .map_or_else(|| "value".to_string(), LitStr::value);
+                quote! { flexml::MapShape::Nested { entry: #entry, key: #key, value: #value } }
+            }
+            _ => quote! { flexml::MapShape::KeyAttribute { entry: #entry, key: #key } },
+        }
+    }
+}
+
+impl Parse for MapArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = Self::default();
+        while !input.is_empty() {
+            let id: Ident = input.parse()?;
+            match id.to_string().as_str() {
+                "key_attribute" | "key_name" | "nested" => args.shape = Some(id),
+                "entry" | "key" | "value" => {
+                    let _eq: Token![=] = input.parse()?;
+                    let name = Some(input.parse()?);
+                    match id.to_string().as_str() {
+                        "entry" => args.entry = name,
+                        "key" => args.key = name,
+                        _ => args.value = name,
                     }
-                    "untagged" => ret.untagged = true,
-                    _ => {}
                 }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        id,
+                        "expected `key_attribute`, `key_name`, `nested`, `entry = ...`, \
+                        `key = ...` or `value = ...`",
+                    ))
+                }
+            }
+            if !input.is_empty() {
+                let _comma: Token![,] = input.parse()?;
             }
         }
 
-        ret
+        let shape = args.shape.as_ref().map(ToString::to_string);
+        let unused = match shape.as_deref() {
+            Some("key_name") => [&args.entry, &args.key, &args.value]
+                .into_iter()
+                .flatten()
+                .next(),
+            Some("nested") => None,
+            _ => args.value.as_ref(),
+        };
+        if let Some(name) = unused {
+            return Err(syn::Error::new_spanned(
+                name,
+                match shape.as_deref() {
+                    Some("key_name") => "`key_name` maps have no entry, key or value names",
+                    _ => "only `nested` maps have a value name",
+                },
+            ));
+        }
+
+        Ok(args)
+    }
+}
+
+/// When to leave a field out, from `#[skip_if]`, `#[skip_none]` and `#[skip_default]`.
+#[derive(Debug, Clone, Default)]
+pub struct SkipRules {
+    /// A path or closure taking a reference to the value, and returning `true` to skip it.
+    pub skip_if: Option<Expr>,
+    pub none: bool,
+    pub default: bool,
+}
+
+impl SkipRules {
+    /// The condition skipping the value behind `reference`, if any. The container's rules
+    /// apply to every field, except `#[skip_none]`, which only covers `Option` fields.
+    pub fn condition(
+        &self,
+        container: &Self,
+        ty: &Type,
+        reference: &TokenStream,
+    ) -> Option<TokenStream> {
+        let mut conditions = Vec::new();
+        if let Some(skip_if) = &self.skip_if {
+            conditions.push(quote! { (#skip_if)(#reference) });
+        }
+        if self.none || (container.none && type_is_option(ty)) {
+            conditions.push(quote! { ::core::option::Option::is_none(#reference) });
+        }
+        if self.default || container.default {
+            conditions.push(quote! {
+                ::core::cmp::PartialEq::eq(#reference, &<#ty as ::core::default::Default>::default())
+            });
+        }
+
+        (!conditions.is_empty()).then(|| quote! { #(#conditions)||* })
+    }
+}
+
+/// The argument of `#[with]`. A lone identifier names a method, on elements and attributes
+/// alike, while anything else - a path or a closure - is called with a reference to the value.
+#[derive(Debug, Clone)]
+pub enum WithArg {
+    Method(Ident),
+    Function(Expr),
+}
+
+impl WithArg {
+    /// An expression converting the value behind `reference`, into `flexml::XML` for elements
+    /// or an attribute value for attributes. Free functions in scope are named by path, e.g.
+    /// `self::f`, as a lone identifier is always a method.
+    pub fn convert(&self, reference: &TokenStream) -> TokenStream {
+        match self {
+            Self::Method(method) => quote! { (#reference).#method() },
+            Self::Function(f) => quote! { (#f)(#reference) },
+        }
+    }
+}
+
+impl Parse for WithArg {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let expr: Expr = input.parse()?;
+        if let Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) = &expr
+        {
+            if let Some(ident) = path.get_ident() {
+                return Ok(Self::Method(ident.clone()));
+            }
+        }
+        match expr {
+            Expr::Path(_) | Expr::Closure(_) => Ok(Self::Function(expr)),
+            _ => Err(syn::Error::new_spanned(
+                expr,
+                "#[with] takes a method name, a path to a function, or a closure",
+            )),
+        }
+    }
+}
+
+/// An argument of `#[attribute(...)]`.
+enum AttributeArg {
+    Case(CaseArg),
+    With(WithArg),
+    Display,
+}
+
+impl Parse for AttributeArg {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input
+            .fork()
+            .parse::<Ident>()
+            .is_ok_and(|id| id == "display")
+        {
+            let _display: Ident = input.parse()?;
+            return Ok(Self::Display);
+        }
+        if input.fork().parse::<Ident>().is_ok_and(|id| id == "with") {
+            let _with: Ident = input.parse()?;
+            let _eq: Token![=] = input.parse()?;
+            return input.parse().map(Self::With);
+        }
+        input.parse().map(Self::Case)
+    }
+}
+
+/// The argument of `#[case]` and `#[case_all]` - either a case string, or a `fn(&str) -> String`
+/// for `flexml::Case::Custom`.
+#[derive(Debug, Clone)]
+pub enum CaseArg {
+    /// A scheme built into `flexml::Case`, by variant.
+    Builtin(Ident, CaseFn),
+    Custom(Expr),
+}
+
+impl CaseArg {
+    /// A `flexml::Case` expression.
+    pub fn to_case_tokens(&self) -> TokenStream {
+        match self {
+            Self::Builtin(variant, _) => quote! { flexml::Case::#variant },
+            Self::Custom(f) => quote! { flexml::Case::Custom(#f) },
+        }
+    }
+
+    /// An expression for `name` in this case. Built-in schemes are applied during expansion.
+    pub fn apply(&self, name: &str) -> TokenStream {
+        match self {
+            Self::Builtin(_, f) => {
+                let name = f(name);
+                quote! { #name }
+            }
+            Self::Custom(f) => quote! { flexml::Case::Custom(#f).apply(#name) },
+        }
+    }
+}
+
+impl Parse for CaseArg {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if !input.peek(LitStr) {
+            return input.parse().map(Self::Custom);
+        }
+
+        let case: LitStr = input.parse()?;
+        let (variant, f) =
+            builtin_case(&case.value()).map_err(|e| syn::Error::new_spanned(&case, e))?;
+        Ok(Self::Builtin(Ident::new(variant, case.span()), f))
+    }
+}
+
+type CaseFn = fn(&str) -> String;
+
+/// Mirrors `flexml::Case::from_str`, returning the matching variant and heck conversion.
+fn builtin_case(case: &str) -> Result<(&'static str, CaseFn), String> {
+    Ok(match case {
+        "KebabCase" | "kebab-case" | "kebab-kase" => ("Kebab", ToKebabCase::to_kebab_case),
+        "LowerCamelCase" | "lowerCamelCase" => {
+            ("LowerCamel", ToLowerCamelCase::to_lower_camel_case)
+        }
+        "ShoutyKebabCase" | "SHOUTY-KEBAB-CASE" => {
+            ("ShoutyKebab", ToShoutyKebabCase::to_shouty_kebab_case)
+        }
+        // "snek" - What chicanery, what shenanigans - and dare I say it - what tomfoolery!
+        "ShoutySnakeCase" | "SHOUTY_SNAKE_CASE" | "ShoutySnekCase" | "SHOUTY_SNEK_CASE" => {
+            ("ShoutySnake", ToShoutySnakeCase::to_shouty_snake_case)
+        }
+        "SnakeCase" | "snake_case" | "SnekCase" | "snek_case" => {
+            ("Snake", ToSnakeCase::to_snake_case)
+        }
+
+        "TitleCase" | "Title Case" => {
+            return Err("XML does not allow the 'Title Case' casing scheme.".to_string())
+        }
+        "TrainCase" | "Train-Case" => ("Train", ToTrainCase::to_train_case),
+        "UpperCamelCase" | "PascalCase" => ("UpperCamel", ToUpperCamelCase::to_upper_camel_case),
+        r => return Err(format!("Unknown case '{r}'")),
+    })
+}
+
+/// The argument of `#[namespace]` - either the name of a namespace declared with
+/// `#[namespaces]` on the same type, or a path to a `flexml::XMLNamespaceDef`.
+#[derive(Debug, Clone)]
+pub enum NamespaceRef {
+    Name(LitStr),
+    Def(Path),
+}
+
+impl Parse for NamespaceRef {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            input.parse().map(Self::Name)
+        } else {
+            input.parse().map(Self::Def)
+        }
+    }
+}
+
+/// Attributes, and the attributes that make no sense alongside them.
+const CONFLICTS: &[(&str, &[&str])] = &[
+    (
+        "text",
+        &[
+            "attribute",
+            "case",
+            "into",
+            "name",
+            "namespace",
+            "tag_attribute",
+            "unit_repr",
+            "untagged",
+            "with",
+        ],
+    ),
+    (
+        "attributes",
+        &["attribute", "case", "flatten", "name", "namespace", "with"],
+    ),
+    ("into", &["unit_repr", "untagged", "with"]),
+    ("tag_attribute", &["untagged"]),
+    ("with", &["unit_repr", "untagged"]),
+    ("wrap", &["attribute", "attributes", "flatten", "text"]),
+    ("item_name", &["name"]),
+    ("sequence", &["attribute", "attributes", "flatten", "text"]),
+    (
+        "map",
+        &[
+            "attribute",
+            "attributes",
+            "case",
+            "flatten",
+            "item_name",
+            "name",
+            "sequence",
+            "text",
+            "with",
+        ],
+    ),
+    (
+        "any_attributes",
+        &[
+            "any",
+            "attribute",
+            "attributes",
+            "case",
+            "flatten",
+            "map",
+            "name",
+            "namespace",
+            "sequence",
+            "text",
+            "with",
+            "wrap",
+        ],
+    ),
+    (
+        "any",
+        &[
+            "attribute",
+            "attributes",
+            "case",
+            "flatten",
+            "item_name",
+            "map",
+            "name",
+            "namespace",
+            "text",
+            "with",
+        ],
+    ),
+];
+
+/// Every helper attribute registered by the derive.
+pub const KNOWN_ATTRIBUTES: &[&str] = &[
+    "any",
+    "any_attributes",
+    "attribute",
+    "attributes",
+    "bound",
+    "case",
+    "case_all",
+    "name",
+    "namespace",
+    "namespaces",
+    "sequence",
+    "declaration",
+    "flatten",
+    "into",
+    "item_name",
+    "map",
+    "with",
+    "unit_repr",
+    "text",
+    "skip_default",
+    "skip_if",
+    "skip_none",
+    "tag_attribute",
+    "unserialized",
+    "untagged",
+    "wrap",
+];
+
+/// Reports each attribute in `ids` found in `attrs` as unsupported by the `derive` macro.
+pub fn reject(attrs: &[Attribute], ids: &[&str], derive: &str, errors: &mut Errors) {
+    for attr in attrs {
+        if let Some(id) = attr.path().get_ident() {
+            if ids.iter().any(|rejected| id == rejected) {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    format!("#[{id}] is not supported by {derive}"),
+                ));
+            }
+        }
+    }
+}
+
+/// Collects every error found while expanding, so they can all be reported at once.
+#[derive(Default)]
+pub struct Errors(Option<syn::Error>);
+
+impl Errors {
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    pub fn finish(self) -> syn::Result<()> {
+        self.0.map_or(Ok(()), Err)
     }
 }
 
@@ -187,29 +794,27 @@ impl Parse for DeclarationFormats {
             1 => {
                 let val = args[0].value();
                 if val.contains('.') {
-                    match Self::version_string(&val) {
-                        Ok((major, minor)) => return Ok(Self::XmlVersion(major, minor)),
-                        Err(e) => return Err(input.error(e)),
-                    }
+                    return Self::version_string(&val)
+                        .map(|(major, minor)| Self::XmlVersion(major, minor))
+                        .map_err(|e| syn::Error::new_spanned(&args[0], e));
                 }
 
-                match Self::encoding_string(&val) {
-                    Ok(enc) => Ok(Self::XmlEncoding(enc)),
-                    Err(e) => Err(input.error(e)),
-                }
+                Self::encoding_string(&val)
+                    .map(Self::XmlEncoding)
+                    .map_err(|e| syn::Error::new_spanned(&args[0], e))
             }
             2 => {
-                let (major, minor) = match Self::version_string(&args[0].value()) {
-                    Ok((major, minor)) => (major, minor),
-                    Err(e) => return Err(input.error(e)),
-                };
-
-                match Self::encoding_string(&args[1].value()) {
-                    Ok(enc) => Ok(Self::Xml(major, minor, enc)),
-                    Err(e) => Err(input.error(e)),
-                }
+                let (major, minor) = Self::version_string(&args[0].value())
+                    .map_err(|e| syn::Error::new_spanned(&args[0], e))?;
+
+                Self::encoding_string(&args[1].value())
+                    .map(|enc| Self::Xml(major, minor, enc))
+                    .map_err(|e| syn::Error::new_spanned(&args[1], e))
             }
-            _ => Err(input.error("Expected at most two string literals")),
+            _ => Err(syn::Error::new_spanned(
+                &args,
+                "Expected at most two string literals",
+            )),
         }
     }
 }
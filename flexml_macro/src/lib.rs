@@ -1,21 +1,26 @@
 mod derive_attributes;
+mod xml_attribute_group;
+mod xml_attribute_value;
 mod xml_enum;
 mod xml_struct;
 
 extern crate proc_macro;
 
-use std::fmt::Display;
-
-use derive_attributes::DeriveAttributes;
-use heck::{
-    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase, ToTrainCase,
-    ToUpperCamelCase,
+use derive_attributes::{
+    reject, AttributePosition, CaseArg, DeriveAttributes, Errors, NamespaceRef, SkipRules,
+    TagAttribute, WithArg,
 };
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::parse::{Parse, ParseStream, Result};
-use syn::{parenthesized, Lit, TypePath};
+use syn::punctuated::Punctuated;
+use syn::{
+    parenthesized, parse_quote, GenericArgument, Generics, Ident, ImplGenerics, Lit, PathArguments,
+    Type, TypeGenerics, TypeParamBound, WhereClause, WherePredicate,
+};
 use syn::{parse_macro_input, DeriveInput, LitStr, Token};
+use xml_attribute_group::AttributeGroupHandler;
+use xml_attribute_value::AttributeValueHandler;
 use xml_enum::EnumHandler;
 use xml_struct::StructHandler;
 
@@ -35,156 +40,613 @@ impl Parse for NamespaceTuple {
     }
 }
 
-/// # Panics
-/// Will panic if met with an unhandled data type. Supported types are Struct and Enum.
+/// Derives `TryIntoXML`, and with it `IntoXML`, for structs and enums, or any type delegating
+/// with `#[into]` or `#[with]`. Misused attributes and other unions are reported as compile
+/// errors.
+///
+/// `#[text]` enums get `ToXMLAttributeValue` as well.
 #[proc_macro_derive(
     ToXML,
     attributes(
+        any,
+        any_attributes,
         attribute,
+        attributes,
+        bound,
         case,
         case_all,
         name,
         namespace,
         namespaces,
         declaration,
+        flatten,
+        into,
         with,
         unit_repr,
+        skip_default,
+        skip_if,
+        skip_none,
+        text,
         unserialized,
-        untagged
+        untagged,
+        wrap,
+        item_name,
+        map,
+        sequence,
+        tag_attribute
     )
 )]
 pub fn xml_node_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let name = &input.ident;
-    let mut generic_tokens = Vec::new();
+    let mut errors = Errors::default();
 
-    let generics = &input.generics;
-    for lifetime in generics.lifetimes() {
-        generic_tokens.push(quote! {#lifetime,});
-    }
+    let xml_attributes = XMLAttributes::process_xml_attributes(&input, &mut errors);
 
-    if !generic_tokens.is_empty() {
-        generic_tokens.insert(0, quote! {<});
-        generic_tokens.push(quote! {>});
-    }
+    let generics =
+        xml_attributes.bounded_generics(&input.generics, &parse_quote!(flexml::TryIntoXML));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // `#[text]` enums are attribute values as well, rendered the same way.
+    let mut attribute_value = None;
+    let expanded_body = match &input.data {
+        _ if xml_attributes.delegate.is_some() => xml_attributes.delegate_tokens(),
+        syn::Data::Struct(data_struct) => {
+            if let Some(tag) = &xml_attributes.tag_attribute {
+                errors.push(syn::Error::new(
+                    tag.span(),
+                    "#[tag_attribute] is only supported on enums",
+                ));
+            }
+            if xml_attributes.text {
+                errors.push(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[text] is only supported on fields and enums with unit variants",
+                ));
+            }
+            StructHandler::expand_tokens(data_struct, &xml_attributes, &mut errors)
+        }
+        syn::Data::Enum(data_enum) if xml_attributes.text => {
+            let value = AttributeValueHandler::expand_tokens(
+                data_enum,
+                &xml_attributes,
+                "#[text] enums can only have unit variants",
+                &mut errors,
+            );
+            attribute_value = Some(value);
+            quote! {
+                flexml::XML::Text(::std::string::String::from(
+                    flexml::ToXMLAttributeValue::to_xml_attribute_value(self)
+                        .unwrap_or_default(),
+                ))
+            }
+        }
+        syn::Data::Enum(data_enum) => {
+            EnumHandler::expand_tokens(data_enum, &xml_attributes, &mut errors)
+        }
+        syn::Data::Union(data_union) => {
+            errors.push(syn::Error::new_spanned(
+                data_union.union_token,
+                "ToXML cannot be derived for unions",
+            ));
+            TokenStream::new()
+        }
+    };
 
-    let xml_attributes = XMLAttributes::process_xml_attributes(&input);
+    // The impl is still emitted on errors, so uses of the type don't add errors of their own.
+    let errors = errors.finish();
+    let attribute_value_impl = attribute_value.map(|value| {
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let value = if errors.is_ok() {
+            quote! { ::core::option::Option::Some(#value) }
+        } else {
+            quote! { ::core::option::Option::None }
+        };
+        attribute_value_impl(name, &impl_generics, &ty_generics, where_clause, &value)
+    });
+    let fn_body = match errors {
+        // There's no value of an empty enum to serialize.
+        Ok(()) if matches!(&input.data, syn::Data::Enum(e) if e.variants.is_empty()) => {
+            quote! { match *self {} }
+        }
+        Ok(()) => {
+            let ns_tokens = &xml_attributes.namespaces_tokens;
+            quote! {
+                #[allow(unused_imports)]
+                use flexml::TryIntoXML as _;
+
+                #(#ns_tokens)*
+
+                ::core::result::Result::Ok(#expanded_body)
+            }
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    proc_macro::TokenStream::from(quote! {
+        #[automatically_derived]
+        impl #impl_generics flexml::TryIntoXML for #name #ty_generics #where_clause {
+            #[allow(clippy::needless_question_mark)]
+            fn try_to_xml(&self) -> ::core::result::Result<flexml::XML, flexml::XMLError> {
+                #fn_body
+            }
+        }
 
-    let ns_tokens = &xml_attributes.namespaces_tokens;
+        #attribute_value_impl
+    })
+}
+
+/// Derives `ToXMLAttributes` for structs whose fields are all attributes, or `#[attributes]`
+/// groups of their own.
+#[proc_macro_derive(
+    ToXMLAttributes,
+    attributes(
+        any_attributes,
+        attribute,
+        attributes,
+        bound,
+        case,
+        case_all,
+        name,
+        namespace,
+        namespaces,
+        skip_default,
+        skip_if,
+        skip_none,
+        unserialized,
+        with,
+        // Not supported in groups, but claimed so they get a targeted error
+        flatten,
+        text
+    )
+)]
+pub fn xml_attributes_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let name = &input.ident;
+    let mut errors = Errors::default();
+
+    reject(&input.attrs, &["with"], "ToXMLAttributes", &mut errors);
+    let xml_attributes = XMLAttributes::process_xml_attributes(&input, &mut errors);
+
+    let generics = xml_attributes
+        .bounded_generics(&input.generics, &parse_quote!(flexml::ToXMLAttributeValue));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let expanded_body = match &input.data {
         syn::Data::Struct(data_struct) => {
-            StructHandler::expand_tokens(data_struct, &xml_attributes)
+            AttributeGroupHandler::expand_tokens(data_struct, &xml_attributes, &mut errors)
+        }
+        syn::Data::Enum(syn::DataEnum { enum_token, .. }) => {
+            errors.push(syn::Error::new_spanned(
+                enum_token,
+                "ToXMLAttributes can only be derived for structs",
+            ));
+            TokenStream::new()
+        }
+        syn::Data::Union(syn::DataUnion { union_token, .. }) => {
+            errors.push(syn::Error::new_spanned(
+                union_token,
+                "ToXMLAttributes can only be derived for structs",
+            ));
+            TokenStream::new()
         }
-        syn::Data::Enum(data_enum) => EnumHandler::expand_tokens(data_enum, &xml_attributes),
-        syn::Data::Union(_) => panic!("Not implemented"),
     };
-    proc_macro::TokenStream::from(quote! {
-        impl #(#generic_tokens)*  flexml::IntoXML for #name #(#generic_tokens)* {
-            fn to_xml(&self) -> flexml::XML {
+
+    let fn_body = match errors.finish() {
+        Ok(()) => {
+            let ns_tokens = &xml_attributes.namespaces_tokens;
+            quote! {
                 #(#ns_tokens)*
 
-                #expanded_body
+                ::core::result::Result::Ok({ #expanded_body })
+            }
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    proc_macro::TokenStream::from(quote! {
+        #[automatically_derived]
+        impl #impl_generics flexml::ToXMLAttributes for #name #ty_generics #where_clause {
+            fn to_xml_attributes(
+                &self,
+            ) -> ::core::result::Result<::std::vec::Vec<flexml::XMLAttribute>, flexml::XMLError> {
+                #fn_body
             }
         }
     })
 }
 
+/// Derives `ToXMLAttributeValue` for C-like enums, so they can be used as `#[attribute]` fields.
+/// Variants are renamed with `#[name]`, `#[case]` or a container `#[case_all]`.
+#[proc_macro_derive(ToXMLAttributeValue, attributes(case, case_all, name))]
+pub fn xml_attribute_value_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let name = &input.ident;
+    let mut errors = Errors::default();
+
+    let xml_attributes = XMLAttributes::process_xml_attributes(&input, &mut errors);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let expanded_body = match &input.data {
+        syn::Data::Enum(data_enum) => AttributeValueHandler::expand_tokens(
+            data_enum,
+            &xml_attributes,
+            "ToXMLAttributeValue can only be derived for enums with unit variants",
+            &mut errors,
+        ),
+        syn::Data::Struct(syn::DataStruct { struct_token, .. }) => {
+            errors.push(syn::Error::new_spanned(
+                struct_token,
+                "ToXMLAttributeValue can only be derived for enums",
+            ));
+            TokenStream::new()
+        }
+        syn::Data::Union(syn::DataUnion { union_token, .. }) => {
+            errors.push(syn::Error::new_spanned(
+                union_token,
+                "ToXMLAttributeValue can only be derived for enums",
+            ));
+            TokenStream::new()
+        }
+    };
+
+    let fn_body = match errors.finish() {
+        Ok(()) => quote! { ::core::option::Option::Some(#expanded_body) },
+        Err(e) => e.to_compile_error(),
+    };
+
+    proc_macro::TokenStream::from(attribute_value_impl(
+        name,
+        &impl_generics,
+        &ty_generics,
+        where_clause,
+        &fn_body,
+    ))
+}
+
+/// The `ToXMLAttributeValue` impl shared by its derive and `#[text]` enums.
+fn attribute_value_impl(
+    name: &Ident,
+    impl_generics: &ImplGenerics,
+    ty_generics: &TypeGenerics,
+    where_clause: Option<&WhereClause>,
+    fn_body: &TokenStream,
+) -> TokenStream {
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics flexml::ToXMLAttributeValue for #name #ty_generics #where_clause {
+            fn to_xml_attribute_value(
+                &self,
+            ) -> ::core::option::Option<::std::borrow::Cow<'_, str>> {
+                #fn_body
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 struct XMLAttributes {
     alias: Option<String>,
-    case: Option<String>,
-    case_all: Option<String>,
+    /// Replaces the `TryIntoXML` bound otherwise added to every type parameter.
+    bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    case: Option<CaseArg>,
+    case_all: Option<CaseArg>,
+    /// Namespace names declared by `#[namespaces]` on this type.
+    declared_namespaces: Vec<String>,
     name: String,
     namespace_token: Option<TokenStream>,
     namespaces_tokens: Vec<TokenStream>,
     declaration_token: Option<TokenStream>,
+    /// The conversion from `#[into]` or `#[with]`, replacing the derived fields entirely.
+    delegate: Option<TokenStream>,
+    /// Skip rules for every field, from container `#[skip_none]` and `#[skip_default]`.
+    skip: SkipRules,
+    /// The attribute naming each variant, from `#[tag_attribute]`, instead of a variant element.
+    tag_attribute: Option<TagAttribute>,
+    /// Whether a unit-only enum is plain text, from container `#[text]`.
+    text: bool,
     unit_repr: Option<Lit>,
     untagged: bool,
 }
 
 impl XMLAttributes {
-    fn process_xml_attributes(input: &DeriveInput) -> Self {
-        let mut xml_attributes = Self::from(DeriveAttributes::from(&input.attrs));
+    fn process_xml_attributes(input: &DeriveInput, errors: &mut Errors) -> Self {
+        let derive_attributes =
+            DeriveAttributes::parse(&input.attrs, AttributePosition::Container, errors);
+        let namespace = derive_attributes.namespace.clone();
+
+        let mut xml_attributes = Self::from(derive_attributes);
         xml_attributes.name = input.ident.to_string();
+        xml_attributes.namespace_token =
+            namespace.map(|ns| xml_attributes.namespace_tokens(&ns, errors));
         xml_attributes
     }
 
-    fn get_node_tag(&self) -> String {
+    /// `generics` with the `#[bound]` predicates, or `default` on every type parameter.
+    fn bounded_generics(&self, generics: &Generics, default: &TypeParamBound) -> Generics {
+        let mut generics = generics.clone();
+        if let Some(bound) = &self.bound {
+            generics
+                .make_where_clause()
+                .predicates
+                .extend(bound.iter().cloned());
+        } else {
+            for param in generics.type_params_mut() {
+                param.bounds.push(default.clone());
+            }
+        }
+        generics
+    }
+
+    /// Tokens setting `namespace` on a node or attribute. Names must be declared on this type.
+    fn namespace_tokens(&self, namespace: &NamespaceRef, errors: &mut Errors) -> TokenStream {
+        let name = match namespace {
+            NamespaceRef::Name(name) => {
+                if !self.declared_namespaces.contains(&name.value()) {
+                    errors.push(syn::Error::new_spanned(
+                        name,
+                        format!(
+                            "Unknown namespace \"{}\" - declare it with #[namespaces] on this type, \
+                            or pass the path of a flexml::XMLNamespaceDef",
+                            name.value()
+                        ),
+                    ));
+                }
+                quote! { #name }
+            }
+            NamespaceRef::Def(path) => quote! {
+                flexml::XMLNamespaceDef::register(&#path)?
+            },
+        };
+
+        quote! {
+            .namespace(#name)?
+        }
+    }
+
+    /// The delegated conversion, with the container's own name, namespace and declaration.
+    fn delegate_tokens(&self) -> TokenStream {
+        let delegate = &self.delegate;
+        let name = (self.alias.is_some() || self.case.is_some()).then(|| {
+            let tag = self.get_node_tag();
+            quote! { .name(#tag) }
+        });
+        let namespace = &self.namespace_token;
+        let declaration = &self.declaration_token;
+
+        quote! {
+            #delegate #name #namespace #declaration
+        }
+    }
+
+    fn get_node_tag(&self) -> TokenStream {
         match (&self.alias, &self.case) {
-            (Some(alias), _) => alias.clone(),
-            (None, Some(case)) => conv_case(&self.name, case),
-            _ => self.name.clone(),
+            (Some(alias), _) => quote! { #alias },
+            (None, Some(case)) => case.apply(&self.name),
+            _ => {
+                let name = &self.name;
+                quote! { #name }
+            }
         }
     }
 }
 
-#[allow(clippy::fallible_impl_from)] // Panics in macros show as editor errors
-#[allow(clippy::assertions_on_constants)] // Can't use const in the From impl, I don't think it's cleaner to split that bit into a different function.
 impl From<DeriveAttributes> for XMLAttributes {
     fn from(value: DeriveAttributes) -> Self {
-        if value.with.is_some() {
-            assert!(
-                !cfg!(test),
-                "`with` attribute is unsupported on container types \n {:#?}",
-                value.with
-            );
-            panic!("`with` attribute is unsupported on container types")
-        }
-
         Self {
             alias: value.alias,
+            bound: value.bound,
             case: value.case,
             case_all: value.case_all,
+            declared_namespaces: value
+                .namespaces
+                .iter()
+                .map(|NamespaceTuple::Ns { ns, .. }| ns.value())
+                .collect(),
             name: String::new(),
-            namespace_token: value.namespace.map(|namespace| {
-                quote! {
-                    .namespace(#namespace).expect("Failed to set namespace")
-                }
-            }),
-            namespaces_tokens: value.namespaces.iter().map(|ns_tuple| {
-                let NamespaceTuple::Ns{ns, uri} = ns_tuple;
-                quote! {
-                flexml::XMLNamespaces::insert(#ns, #uri).expect("failed to insert namespace");
-            }}).collect(),
+            namespace_token: None,
+            namespaces_tokens: value
+                .namespaces
+                .iter()
+                .map(|ns_tuple| {
+                    let NamespaceTuple::Ns { ns, uri } = ns_tuple;
+                    quote! {
+                        flexml::XMLNamespaces::insert(#ns, #uri)?;
+                    }
+                })
+                .collect(),
             declaration_token: value.declaration.map(|declaration| {
                 let declaration_tokens = declaration.into_tokens();
                 quote! {
                     .declaration( #declaration_tokens )
                 }
             }),
+            delegate: match (value.into, value.with) {
+                (Some(into), _) => Some(quote! {
+                    flexml::TryIntoXML::try_to_xml(
+                        &<#into as ::core::convert::From<&Self>>::from(self),
+                    )?
+                }),
+                (None, Some(with)) => Some(with.convert(&quote! { self })),
+                (None, None) => None,
+            },
+            skip: value.skip,
+            tag_attribute: value.tag_attribute,
+            text: value.text,
             unit_repr: value.unit_repr,
-            untagged: value.untagged
+            untagged: value.untagged,
         }
     }
 }
 
-fn type_is_vec(typepath: &TypePath) -> bool {
-    let segments = &typepath.path.segments;
+fn type_is_option(ty: &Type) -> bool {
+    let Type::Path(typepath) = ty else {
+        return false;
+    };
 
-    segments
+    typepath
+        .path
+        .segments
         .last()
-        .is_some_and(|last_seg| last_seg.ident == "Vec")
+        .is_some_and(|last_seg| last_seg.ident == "Option")
+}
+
+/// `value`, or `skipped` when `condition` holds.
+fn skip_tokens(
+    condition: Option<TokenStream>,
+    value: TokenStream,
+    skipped: &TokenStream,
+) -> TokenStream {
+    match condition {
+        Some(condition) => quote! { if #condition { #skipped } else { #value } },
+        None => value,
+    }
 }
 
-fn conv_case<T: Display, V: Display>(input: T, case: V) -> String {
-    let input = input.to_string();
-    match case.to_string().as_str() {
-        "KebabCase" | "kebab-kase" => input.to_kebab_case(),
-        "LowerCamelCase" | "lowerCamelCase" => input.to_lower_camel_case(),
-        "ShoutyKebabCase" | "SHOUTY-KEBAB-CASE" => input.to_shouty_kebab_case(),
-        // "snek" - What chicanery, what shenanigans - and dare I say it - what tomfoolery!
-        "ShoutySnakeCase" | "SHOUTY_SNAKE_CASE" | "ShoutySnekCase" | "SHOUTY_SNEK_CASE" => {
-            input.to_shouty_snake_case()
+/// The builder calls for a `#[text]` field, unless `skip` holds.
+fn text_tokens(skip: Option<TokenStream>, reference: &TokenStream) -> TokenStream {
+    skip.map_or_else(
+        || quote! { .text(#reference) },
+        |skip| {
+            quote! {
+                .datum(if #skip {
+                    flexml::XML::None
+                } else {
+                    flexml::XML::Text(::std::string::ToString::to_string(#reference))
+                })
+            }
+        },
+    )
+}
+
+/// The builder calls for an `#[attributes]` group, unless `skip` holds.
+fn attribute_group_tokens(skip: Option<TokenStream>, reference: &TokenStream) -> TokenStream {
+    let attributes = skip_tokens(
+        skip,
+        quote! { flexml::ToXMLAttributes::to_xml_attributes(#reference)? },
+        &quote! { ::std::vec::Vec::new() },
+    );
+    quote! { .attributes(#attributes) }
+}
+
+/// Collections whose items are expanded one by one, by the last segment of their path.
+const SEQUENCE_TYPES: &[&str] = &[
+    "ArrayVec",
+    "BTreeSet",
+    "BinaryHeap",
+    "IndexSet",
+    "LinkedList",
+    "SmallVec",
+    "Vec",
+    "VecDeque",
+];
+
+/// How the items of a sequence field are reached and ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sequence {
+    /// Anything `IntoIterator` by reference, in iteration order.
+    Items,
+    /// `Rc<[T]>` and `Arc<[T]>`, iterated through the slice they point to.
+    SharedSlice,
+    /// Sets without an order of their own, sorted by their rendered items as `HashMap` entries
+    /// are by key.
+    Unordered,
+}
+
+impl Sequence {
+    /// Collects `items` into a `Vec<XML>`, converting each item `d` with `item`.
+    fn items_tokens(self, items: &TokenStream, item: &TokenStream) -> TokenStream {
+        let iter = if self == Self::SharedSlice {
+            quote! { (#items).iter() }
+        } else {
+            quote! { ::core::iter::IntoIterator::into_iter(#items) }
+        };
+        let items = quote! {
+            #iter
+                .map(|d| -> ::core::result::Result<flexml::XML, flexml::XMLError> {
+                    ::core::result::Result::Ok(#item)
+                })
+                .collect::<::core::result::Result<Vec<flexml::XML>, flexml::XMLError>>()?
+        };
+        if self == Self::Unordered {
+            quote! {{
+                let mut items = #items;
+                items.sort_by_cached_key(::std::string::ToString::to_string);
+                items
+            }}
+        } else {
+            items
         }
-        "SnakeCase" | "snake_case" | "SnekCase" | "snek_case" => input.to_snake_case(),
+    }
+}
 
-        "TitleCase" | "Title Case" => {
-            panic!("XML does not allow the 'Title Case' casing scheme.")
+/// How a field of type `ty` is expanded item by item, if it's a sequence like `Vec`s, arrays and
+/// slices. Type aliases can't be seen through, so they need `#[sequence]`.
+fn sequence_kind(ty: &Type) -> Option<Sequence> {
+    match ty {
+        Type::Array(_) | Type::Slice(_) => Some(Sequence::Items),
+        Type::Group(group) => sequence_kind(&group.elem),
+        Type::Paren(paren) => sequence_kind(&paren.elem),
+        Type::Reference(reference) => sequence_kind(&reference.elem),
+        Type::Path(typepath) => {
+            let last_seg = typepath.path.segments.last()?;
+            if last_seg.ident == "HashSet" {
+                return Some(Sequence::Unordered);
+            }
+            if SEQUENCE_TYPES.iter().any(|name| last_seg.ident == name) {
+                return Some(Sequence::Items);
+            }
+            // `Box<[T]>` and the like
+            let PathArguments::AngleBracketed(args) = &last_seg.arguments else {
+                return None;
+            };
+            if !matches!(
+                args.args.first(),
+                Some(GenericArgument::Type(Type::Slice(_)))
+            ) {
+                return None;
+            }
+            if last_seg.ident == "Box" {
+                Some(Sequence::Items)
+            } else if last_seg.ident == "Arc" || last_seg.ident == "Rc" {
+                Some(Sequence::SharedSlice)
+            } else {
+                None
+            }
         }
-        "TrainCase" | "Train-Case" => input.to_train_case(),
-        "UpperCamelCase" | "PascalCase" => input.to_upper_camel_case(),
-        r => panic!("Unknown case '{r}'"),
+        _ => None,
     }
 }
+
+/// An `Option<XMLAttribute>` expression, `None` when `value` has no `ToXMLAttributeValue`.
+fn attribute_tokens(
+    key: &TokenStream,
+    value: &TokenStream,
+    namespace: Option<TokenStream>,
+) -> TokenStream {
+    namespace.map_or_else(
+        || quote! { flexml::XMLAttribute::from_value(#key, #value) },
+        |namespace| {
+            quote! {
+                match flexml::XMLAttribute::from_value(#key, #value) {
+                    ::core::option::Option::Some(attribute) => {
+                        ::core::option::Option::Some(attribute #namespace)
+                    }
+                    ::core::option::Option::None => ::core::option::Option::None,
+                }
+            }
+        },
+    )
+}
+
+/// An expression converting the value behind `reference` into `flexml::XML`, through `with` or
+/// `TryIntoXML`.
+fn convert_tokens(with: Option<&WithArg>, reference: &TokenStream) -> TokenStream {
+    with.map_or_else(
+        || quote! { (#reference).try_to_xml()? },
+        |with| with.convert(reference),
+    )
+}
@@ -0,0 +1,110 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{DataStruct, Index};
+
+use crate::derive_attributes::{reject, AttributePosition, Errors};
+use crate::{attribute_tokens, DeriveAttributes, XMLAttributes};
+
+/// Expands `ToXMLAttributes`, where every field is an attribute or a nested `#[attributes]` group.
+pub struct AttributeGroupHandler;
+
+impl AttributeGroupHandler {
+    pub fn expand_tokens(
+        data_struct: &DataStruct,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> TokenStream {
+        let mut field_tokens = Vec::new();
+
+        for (i, field) in data_struct.fields.iter().enumerate() {
+            reject(
+                &field.attrs,
+                &["flatten", "text"],
+                "ToXMLAttributes",
+                errors,
+            );
+            let mut field_attributes =
+                DeriveAttributes::parse(&field.attrs, AttributePosition::Field, errors);
+            if field_attributes.unserialized {
+                continue;
+            }
+
+            let member = field.ident.as_ref().map_or_else(
+                || Index::from(i).into_token_stream(),
+                ToTokens::into_token_stream,
+            );
+
+            let skip = field_attributes.skip.condition(
+                &xml_attributes.skip,
+                &field.ty,
+                &quote! { &self.#member },
+            );
+
+            if field_attributes.attributes || field_attributes.any_attributes {
+                field_tokens.push(skip_statement(
+                    skip,
+                    quote! {
+                        attributes
+                            .extend(flexml::ToXMLAttributes::to_xml_attributes(&self.#member)?);
+                    },
+                ));
+                continue;
+            }
+
+            if field_attributes.case.is_none() {
+                field_attributes.case.clone_from(&xml_attributes.case_all);
+            }
+            let key = match (field_attributes.alias, field_attributes.case, &field.ident) {
+                (Some(alias), _, _) => quote! { #alias },
+                (None, Some(case), Some(ident)) => case.apply(&ident.to_string()),
+                (None, None, Some(ident)) => {
+                    let name = ident.to_string();
+                    quote! { #name }
+                }
+                (None, _, None) => {
+                    errors.push(syn::Error::new_spanned(
+                        field,
+                        "Unnamed fields need a #[name] to be used as attributes",
+                    ));
+                    continue;
+                }
+            };
+
+            let namespace_stream = field_attributes
+                .namespace
+                .map(|ns| xml_attributes.namespace_tokens(&ns, errors));
+            let value = field_attributes.with.as_ref().map_or_else(
+                || quote! { &self.#member },
+                |with| {
+                    let formatted = with.convert(&quote! { &self.#member });
+                    quote! { &#formatted }
+                },
+            );
+            let attribute = attribute_tokens(&key, &value, namespace_stream);
+            field_tokens.push(skip_statement(
+                skip,
+                quote! {
+                    attributes.extend(#attribute);
+                },
+            ));
+        }
+
+        quote! {
+            let mut attributes = ::std::vec::Vec::new();
+            #(#field_tokens)*
+            attributes
+        }
+    }
+}
+
+/// `statement`, only run when `skip` doesn't hold.
+fn skip_statement(skip: Option<TokenStream>, statement: TokenStream) -> TokenStream {
+    match skip {
+        Some(skip) => quote! {
+            if !(#skip) {
+                #statement
+            }
+        },
+        None => statement,
+    }
+}
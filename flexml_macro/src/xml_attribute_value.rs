@@ -0,0 +1,61 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DataEnum, Fields};
+
+use crate::derive_attributes::{AttributePosition, Errors};
+use crate::{DeriveAttributes, XMLAttributes};
+
+/// Expands `ToXMLAttributeValue` for C-like enums, each variant rendering as its name. `#[text]`
+/// enums deriving `ToXML` share it, so their text matches their attribute values.
+pub struct AttributeValueHandler;
+
+impl AttributeValueHandler {
+    /// A `Cow<str>` expression for `self`. `unit_only` is the error for variants with fields.
+    pub fn expand_tokens(
+        data_enum: &DataEnum,
+        xml_attributes: &XMLAttributes,
+        unit_only: &str,
+        errors: &mut Errors,
+    ) -> TokenStream {
+        let mut variant_tokens = Vec::new();
+
+        for variant in &data_enum.variants {
+            if !matches!(variant.fields, Fields::Unit) {
+                errors.push(syn::Error::new_spanned(&variant.fields, unit_only));
+                continue;
+            }
+
+            let mut variant_attributes =
+                DeriveAttributes::parse(&variant.attrs, AttributePosition::Variant, errors);
+            if let (true, Some(unit_repr)) = (xml_attributes.text, &variant_attributes.unit_repr) {
+                errors.push(syn::Error::new_spanned(
+                    unit_repr,
+                    "Variants of #[text] enums render as their name - rename them with #[name] \
+                    instead of #[unit_repr]",
+                ));
+            }
+            if variant_attributes.case.is_none() {
+                variant_attributes.case.clone_from(&xml_attributes.case_all);
+            }
+
+            let ident = &variant.ident;
+            let value = match (variant_attributes.alias, variant_attributes.case) {
+                (Some(alias), _) => quote! { #alias },
+                (None, Some(case)) => case.apply(&ident.to_string()),
+                (None, None) => {
+                    let name = ident.to_string();
+                    quote! { #name }
+                }
+            };
+            variant_tokens.push(quote! {
+                Self::#ident => ::std::borrow::Cow::from(#value),
+            });
+        }
+
+        quote! {
+            match *self {
+                #(#variant_tokens)*
+            }
+        }
+    }
+}
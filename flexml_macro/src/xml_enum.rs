@@ -1,8 +1,12 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{DataEnum, Fields, FieldsNamed, FieldsUnnamed, Ident};
+use syn::{spanned::Spanned, DataEnum, Fields, Ident, Lit, Type};
 
-use crate::{conv_case, DeriveAttributes, XMLAttributes};
+use crate::derive_attributes::{
+    reject, AttributePosition, CaseArg, Errors, NamespaceRef, WithArg, KNOWN_ATTRIBUTES,
+};
+use crate::xml_struct::{FieldAccess, StructHandler};
+use crate::{convert_tokens, DeriveAttributes, XMLAttributes};
 
 #[derive(Debug)]
 pub struct EnumHandler {
@@ -13,18 +17,16 @@ impl EnumHandler {
     pub(crate) fn expand_tokens(
         data_enum: &DataEnum,
         xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
     ) -> TokenStream {
-        let xml_enum_variants = Self::process_fields(data_enum, xml_attributes);
+        let xml_enum_variants = Self::process_fields(data_enum, xml_attributes, errors);
 
         let node_tag = xml_attributes.get_node_tag();
-        if data_enum.variants.is_empty() {
-            let unit_repr = &xml_attributes.unit_repr;
-            quote! {
-                flexml::XML::new(#node_tag) .datum(#unit_repr) ,
-            };
-        }
         let variant_tokens = xml_enum_variants.variant_tokens;
-        if xml_attributes.alias.is_some() || xml_attributes.case.is_some() {
+        // Tagged by attribute, every variant is already the container's element.
+        if xml_attributes.tag_attribute.is_none()
+            && (xml_attributes.alias.is_some() || xml_attributes.case.is_some())
+        {
             let node_ns_token = &xml_attributes.namespace_token;
             let node_declaration_token = &xml_attributes.declaration_token;
 
@@ -46,33 +48,46 @@ impl EnumHandler {
         }
     }
 
-    pub(crate) fn process_fields(data_enum: &DataEnum, xml_attributes: &XMLAttributes) -> Self {
+    pub(crate) fn process_fields(
+        data_enum: &DataEnum,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> Self {
         let mut variant_tokens = Vec::new();
 
         for xml_variant in &data_enum.variants {
-            let mut variant = EnumVariant::from(DeriveAttributes::from(&xml_variant.attrs));
-            variant.untagged = xml_attributes.untagged;
+            let mut variant = EnumVariant::from(DeriveAttributes::parse(
+                &xml_variant.attrs,
+                AttributePosition::Variant,
+                errors,
+            ));
             variant.name = Some(xml_variant.ident.clone());
 
             if variant.case.is_none() {
                 variant.case.clone_from(&xml_attributes.case_all);
             }
 
-            if variant.alias.is_empty() {
-                variant.alias = xml_variant.ident.clone().to_string();
-                if let Some(ref case) = variant.case {
-                    variant.alias = conv_case(variant.alias, case);
+            let tag = match (&variant.alias, &variant.case) {
+                (Some(alias), _) => quote! { #alias },
+                (None, Some(case)) => case.apply(&xml_variant.ident.to_string()),
+                _ => {
+                    let name = xml_variant.ident.to_string();
+                    quote! { #name }
                 }
-            }
+            };
+            variant.opening = variant.opening_tokens(&tag, xml_attributes, errors);
 
             let field_tokens = match &xml_variant.fields {
-                syn::Fields::Named(fields_named) => {
-                    variant.named_fields_to_tokens(fields_named, variant.case_all.as_ref())
-                }
-                syn::Fields::Unnamed(fields_unnamed) => {
-                    variant.unnamed_fields_to_tokens(fields_unnamed, variant.case_all.as_ref())
+                Fields::Unit => variant.unit_fields_to_tokens(xml_attributes),
+                fields => {
+                    if let Some(unit_repr) = &variant.unit_repr {
+                        errors.push(syn::Error::new_spanned(
+                            unit_repr,
+                            "#[unit_repr] is only supported on unit variants",
+                        ));
+                    }
+                    variant.fields_to_tokens(fields, xml_attributes, errors)
                 }
-                syn::Fields::Unit => variant.unit_fields_to_tokens(xml_attributes),
             };
 
             variant_tokens.push(field_tokens);
@@ -84,173 +99,147 @@ impl EnumHandler {
 
 #[derive(Debug)]
 pub struct EnumVariant {
-    alias: String,
-    case: Option<String>,
-    case_all: Option<String>,
+    alias: Option<String>,
+    case: Option<CaseArg>,
+    case_all: Option<CaseArg>,
     name: Option<Ident>,
-    namespace: Option<String>,
-    untagged: bool,
-    with: Option<Ident>,
+    namespace: Option<NamespaceRef>,
+    /// The element every field is added to - the variant's own element, the container's element
+    /// with a `#[tag_attribute]`, or an untagged container.
+    opening: TokenStream,
+    /// The variant's own `#[unit_repr]`, over the enum's.
+    unit_repr: Option<Lit>,
+    with: Option<WithArg>,
 }
 
 impl EnumVariant {
-    fn named_fields_to_tokens(
+    fn opening_tokens(
         &self,
-        fields: &FieldsNamed,
-        case_all: Option<&String>,
+        tag: &TokenStream,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
     ) -> TokenStream {
-        let fields = std::convert::Into::<Fields>::into(fields.clone());
-
-        let variant_name = &self.name;
-        let conv_call = self
-            .with
-            .as_ref()
-            .map_or_else(|| quote! {.to_xml()}, |with| quote! {.#with()});
+        if xml_attributes.untagged {
+            return quote! { flexml::XML::new_untagged() };
+        }
 
-        let mut field_names = Vec::new();
-        let mut field_tokens = Vec::new();
-        for field in fields {
-            let field_name = field
-                .ident
+        let Some(tag_attribute) = &xml_attributes.tag_attribute else {
+            let namespace_stream = self
+                .namespace
                 .as_ref()
-                .expect("Field identifier is None - impossible case");
-            field_names.push(field_name.clone());
-
-            let mut field_attributes = DeriveAttributes::from(&field.attrs);
-            if field_attributes.case.is_none() {
-                field_attributes.case = case_all.cloned();
-            }
-
-            let alias = match (field_attributes.alias, field_attributes.case) {
-                (Some(alias), _) => alias,
-                (_, Some(ref case)) => conv_case(field_name, case),
-                _ => format!("{field_name}"),
+                .map(|ns| xml_attributes.namespace_tokens(ns, errors));
+            return quote! { flexml::XML::new(#tag) #namespace_stream };
+        };
+
+        if let Some(namespace) = &self.namespace {
+            let span = match namespace {
+                NamespaceRef::Name(name) => name.span(),
+                NamespaceRef::Def(path) => path.span(),
             };
-
-            let namespace_stream = field_attributes.namespace.map(|ns| {
-                quote! {
-                    .namespace(#ns).expect("Failed to set node namespace.")
-                }
-            });
-            field_tokens.push(if self.untagged {
-                quote! { flexml::XML::new_container().datum(#field_name #conv_call)#namespace_stream }
-            } else {
-                quote! { flexml::XML::new(#alias).datum(#field_name #conv_call)#namespace_stream}
-            });
+            errors.push(syn::Error::new(
+                span,
+                "Variants of a #[tag_attribute] enum share the enum's element, so they can't \
+                have a #[namespace] of their own",
+            ));
         }
-        if self.untagged {
-            quote! {Self::#variant_name{#(#field_names,)*} => #((#field_tokens))* ,}
-        } else {
-            let variant_alias = &self.alias;
-
-            let namespace_stream = self.namespace.as_ref().map(|ns| {
-                quote! {
-                    .namespace(#ns).expect("Failed to set node namespace.")
-                }
-            });
-            quote! {
-                Self::#variant_name{#(#field_names,)*} =>
-                flexml::XML::new(#variant_alias) #namespace_stream #(.datum(#field_tokens))* ,
-            }
+        let node_tag = xml_attributes.get_node_tag();
+        let namespace_stream = &xml_attributes.namespace_token;
+        let declaration_stream = &xml_attributes.declaration_token;
+        let attribute = tag_attribute.to_attribute_tokens(tag);
+        quote! {
+            flexml::XML::new(#node_tag) #namespace_stream #declaration_stream #attribute
         }
     }
 
-    fn unnamed_fields_to_tokens(
+    /// The match arm for a variant with fields, which are expanded as a struct's fields are.
+    /// Named fields are always elements of their own.
+    fn fields_to_tokens(
         &self,
-        fields: &FieldsUnnamed,
-        case_all: Option<&String>,
+        fields: &Fields,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
     ) -> TokenStream {
-        let fields = std::convert::Into::<Fields>::into(fields.clone());
-
         let variant_name = &self.name;
+        let named = matches!(fields, Fields::Named(_));
+
+        // The conversion replaces the fields entirely, so their attributes would go unused.
+        if let Some(with) = &self.with {
+            for field in fields {
+                reject(
+                    &field.attrs,
+                    KNOWN_ATTRIBUTES,
+                    "fields of #[with] variants",
+                    errors,
+                );
+            }
+            let converted = with.convert(&quote! { self });
+            return quote! { Self::#variant_name { .. } => #converted, };
+        }
 
-        let matching = (0..fields.len())
-            .map(|i| {
-                let n = format_ident!("n{i}");
-                quote! {#n}
-            })
-            .collect::<Vec<TokenStream>>();
-
-        let mut field_tokens = Vec::new();
+        let mut handler = StructHandler::default();
+        let mut bindings = Vec::new();
         for (i, field) in fields.iter().enumerate() {
-            let mut field_attributes = DeriveAttributes::from(&field.attrs);
-            if field_attributes.case.is_none() {
-                field_attributes.case = case_all.cloned();
+            let binding = field.ident.clone().unwrap_or_else(|| format_ident!("n{i}"));
+            // Items of a referenced sequence are reached through the reference itself.
+            let items = if matches!(field.ty, Type::Reference(_)) {
+                quote! { *#binding }
+            } else {
+                quote! { #binding }
+            };
+            let access = FieldAccess {
+                reference: quote! { #binding },
+                items,
+                case_all: self.case_all.as_ref(),
+                element: named,
+            };
+            if handler.add_field(field, i, &access, xml_attributes, errors) {
+                bindings.push(quote! { #binding });
+            } else if !named {
+                bindings.push(quote! { _ });
             }
-            let n = format_ident!("n{i}");
-
-            let conv_call = self
-                .with
-                .as_ref()
-                .map_or_else(|| quote! {.to_xml()}, |with| quote! {.#with()});
-            let namespace_stream = field_attributes.namespace.map(|ns| {
-                quote! {
-                    .namespace(#ns).expect("Failed to set node namespace.")
-                }
-            });
-            field_tokens.push(quote! {#n #conv_call #namespace_stream});
         }
 
-        #[allow(clippy::option_if_let_else)] // I think the match reads better here
-        match &self.with {
-            Some(ref with) => quote! {Self::#variant_name(#(#matching,)*) => self.#with(),},
-            None => {
-                if self.untagged {
-                    quote! {Self::#variant_name(#(#matching,)*) => flexml::XML::new_untagged()#(.datum(#field_tokens))* ,}
-                } else {
-                    let variant_alias = &self.alias;
-
-                    let namespace_stream = self.namespace.as_ref().map(|ns| {
-                        quote! {
-                            .namespace(#ns).expect("Failed to set node namespace.")
-                        }
-                    });
-                    quote! {Self::#variant_name(#(#matching,)*) => flexml::XML::new(#variant_alias) #namespace_stream #(.datum(#field_tokens))*,}
-                }
-            }
+        let pattern = if named {
+            quote! { Self::#variant_name { #(#bindings,)* .. } }
+        } else {
+            quote! { Self::#variant_name(#(#bindings,)*) }
+        };
+        let opening = &self.opening;
+        let attribute_fields = &handler.attribute_fields;
+        let node_fields = &handler.node_fields;
+        quote! {
+            #pattern => #opening #(#attribute_fields)* #(#node_fields)*,
         }
     }
 
     fn unit_fields_to_tokens(&self, xml_attributes: &XMLAttributes) -> TokenStream {
         let variant_name = &self.name;
-        let conv_call = self
-            .with
+        let opening = &self.opening;
+        // Without a `#[unit_repr]`, a unit variant is just its (possibly empty) element.
+        let unit_repr = self
+            .unit_repr
             .as_ref()
-            .map_or_else(|| quote! {.to_xml()}, |with| quote! {.#with()});
-
-        let unit_repr = &xml_attributes.unit_repr;
-        if self.untagged {
-            quote! {Self::#variant_name => flexml::XML::new_untagged().datum(#unit_repr #conv_call) ,}
-        } else {
-            let variant_alias = &self.alias;
-
-            let namespace_stream = self.namespace.as_ref().map(|ns| {
-                quote! {
-                    .namespace(#ns).expect("Failed to set node namespace.")
-                }
-            });
-            quote! {
-                Self::#variant_name =>
-                flexml::XML::new(#variant_alias) #namespace_stream .datum(#unit_repr #conv_call) ,
-            }
+            .or(xml_attributes.unit_repr.as_ref());
+        let datum = unit_repr.map(|unit_repr| {
+            let converted = convert_tokens(self.with.as_ref(), &quote! { &#unit_repr });
+            quote! { .datum(#converted) }
+        });
+        quote! {
+            Self::#variant_name => #opening #datum ,
         }
     }
 }
 
 impl From<DeriveAttributes> for EnumVariant {
     fn from(value: DeriveAttributes) -> Self {
-        assert!(
-            !value.untagged,
-            "Incorrect placement of #[untagged] attribute, \
-            it should be on the containing enum."
-        );
         Self {
-            alias: value.alias.unwrap_or_default(),
+            alias: value.alias,
             case: value.case,
             case_all: value.case_all,
             name: None,
             namespace: value.namespace,
-            untagged: false,
+            opening: TokenStream::new(),
+            unit_repr: value.unit_repr,
             with: value.with,
         }
     }
@@ -1,8 +1,14 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{DataStruct, Ident, Index, Type, TypePath};
+use syn::{DataStruct, Field, Index, LitStr, Type};
 
-use crate::{conv_case, type_is_vec, DeriveAttributes, XMLAttributes};
+use crate::derive_attributes::{
+    AttributePosition, CaseArg, Errors, MapArgs, NamespaceRef, SkipRules, WithArg, WrapArgs,
+};
+use crate::{
+    attribute_group_tokens, attribute_tokens, convert_tokens, sequence_kind, skip_tokens,
+    text_tokens, DeriveAttributes, Sequence, XMLAttributes,
+};
 
 #[derive(Default)]
 pub struct StructHandler {
@@ -14,8 +20,9 @@ impl StructHandler {
     pub(crate) fn expand_tokens(
         data_struct: &DataStruct,
         xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
     ) -> TokenStream {
-        let mut xml_field_attributes = Self::process_fields(data_struct, xml_attributes);
+        let mut xml_field_attributes = Self::process_fields(data_struct, xml_attributes, errors);
 
         let mut attr_tokens = Vec::new();
         let mut node_tokens = Vec::new();
@@ -41,7 +48,11 @@ impl StructHandler {
                 #(#node_tokens)*
         }
     }
-    pub(crate) fn process_fields(data_struct: &DataStruct, xml_attributes: &XMLAttributes) -> Self {
+    pub(crate) fn process_fields(
+        data_struct: &DataStruct,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> Self {
         let mut field_token_streams = Self::default();
 
         if let (Some(unit_repr), true) = (&xml_attributes.unit_repr, data_struct.fields.is_empty())
@@ -54,119 +65,343 @@ impl StructHandler {
         }
 
         for (i, xml_field) in data_struct.fields.iter().enumerate() {
-            let mut struct_field = StructField::from(DeriveAttributes::from(&xml_field.attrs));
-            if struct_field.unserialized {
-                continue;
-            }
-            if struct_field.case.is_none() {
-                struct_field.case.clone_from(&xml_attributes.case_all);
-            }
-
-            struct_field.name = xml_field
-                .ident
-                .clone()
-                .map_or_else(|| i.to_string(), |s| s.to_string());
-
-            let name = xml_field.ident.as_ref().map_or_else(
+            let member = xml_field.ident.as_ref().map_or_else(
                 || Index::from(i).into_token_stream(),
                 ToTokens::into_token_stream,
             );
+            // Items of a referenced sequence are reached through the reference itself.
+            let items = if matches!(xml_field.ty, Type::Reference(_)) {
+                quote! { self.#member }
+            } else {
+                quote! { &self.#member }
+            };
+            field_token_streams.add_field(
+                xml_field,
+                i,
+                &FieldAccess {
+                    reference: quote! { &self.#member },
+                    items,
+                    case_all: xml_attributes.case_all.as_ref(),
+                    element: false,
+                },
+                xml_attributes,
+                errors,
+            );
+        }
 
-            if let Type::Path(path) = xml_field.ty.clone() {
-                struct_field.ty = Some(path);
-            }
-
-            if struct_field.attribute {
-                let field_str = match (struct_field.alias, struct_field.case) {
-                    (Some(alias), _) => alias,
-                    (None, Some(case)) => conv_case(&struct_field.name, case),
-                    _ => struct_field.name.clone(),
-                };
-
-                let namespace_stream = struct_field.namespace.map(|ns| {
-                    quote! {
-                        .namespace(#ns).expect("Failed to set node namespace.")
-                    }
-                });
+        field_token_streams
+    }
 
-                field_token_streams.attribute_fields.push(quote! {
-                    .attribute(flexml::XMLAttribute::new(#field_str, &self. #name) #namespace_stream)
-                });
-            } else {
-                let alias = struct_field.alias.map_or_else(
-                    || quote! {},
-                    |alias| {
-                        quote! {
-                            .name(#alias)
-                        }
-                    },
-                );
+    /// Adds the builder calls for `xml_field`, the `index`th field, to the attribute or node
+    /// fields. Returns `false` if the field is `#[unserialized]`.
+    pub(crate) fn add_field(
+        &mut self,
+        xml_field: &Field,
+        index: usize,
+        access: &FieldAccess,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> bool {
+        let mut struct_field = StructField::from(DeriveAttributes::parse(
+            &xml_field.attrs,
+            AttributePosition::Field,
+            errors,
+        ));
+        if struct_field.unserialized {
+            return false;
+        }
+        if struct_field.case.is_none() {
+            struct_field.case = access.case_all.cloned();
+        }
 
-                let node_case = struct_field.case.map_or_else(
-                    || quote! {},
-                    |case| {
-                        quote! {
-                            .case(#case)
-                        }
-                    },
-                );
+        struct_field.name = xml_field
+            .ident
+            .clone()
+            .map_or_else(|| index.to_string(), |s| s.to_string());
+        if let Some(sequence) = sequence_kind(&xml_field.ty) {
+            struct_field.sequence = Some(sequence);
+        }
 
-                let namespace_stream = struct_field.namespace.map(|ns| {
-                    quote! {
-                        .namespace(#ns).expect("Failed to set node namespace.")
-                    }
-                });
-                let cast_stream = struct_field
-                    .with
-                    .map_or_else(|| quote! {.to_xml()}, |with| quote! {.#with()});
-
-                let xml_type = &struct_field.ty.unwrap_or_else(|| {
-                    panic!("Could not determine type of field {}", struct_field.name)
-                });
-
-                let stream = if type_is_vec(xml_type) {
-                    quote! {
-                        .data(
-                            self.#name.iter()
-                                .map(|d| d #cast_stream #alias #node_case #namespace_stream)
-                                .collect::<Vec<flexml::XML>>().as_slice()
-                        )
-                    }
-                } else {
-                    quote! {
-                        .datum(self.#name #cast_stream #alias #node_case #namespace_stream)
-                    }
-                };
+        let reference = &access.reference;
+        let skip = struct_field
+            .skip
+            .condition(&xml_attributes.skip, &xml_field.ty, reference);
 
-                field_token_streams.node_fields.push(stream);
+        if struct_field.attributes {
+            self.attribute_fields
+                .push(attribute_group_tokens(skip, reference));
+        } else if struct_field.text {
+            self.node_fields.push(text_tokens(skip, reference));
+        } else if struct_field.attribute {
+            if xml_field.ident.is_none() && struct_field.alias.is_none() {
+                errors.push(syn::Error::new_spanned(
+                    xml_field,
+                    "Unnamed fields need a #[name] to be used as attributes",
+                ));
+                return true;
+            }
+            let attribute = skip_tokens(
+                skip,
+                struct_field.attribute_tokens(reference, xml_attributes, errors),
+                &quote! { ::core::option::Option::None },
+            );
+            self.attribute_fields.push(quote! {
+                .attributes(#attribute)
+            });
+        } else {
+            if access.element && !struct_field.flatten && !struct_field.any {
+                struct_field.wrap_as_element();
             }
+            let stream =
+                struct_field.node_tokens(reference, &access.items, skip, xml_attributes, errors);
+            self.node_fields.push(stream);
         }
-
-        field_token_streams
+        true
     }
 }
 
+/// How a field's value is reached, for [`StructHandler::add_field`].
+pub struct FieldAccess<'a> {
+    /// A reference to the value, like `&self.field` or a variant's binding.
+    pub reference: TokenStream,
+    /// What `IntoIterator` is called on for sequences.
+    pub items: TokenStream,
+    pub case_all: Option<&'a CaseArg>,
+    /// Whether element fields are always wrapped in an element named after the field, as the
+    /// named fields of enum variants are.
+    pub element: bool,
+}
+
+#[allow(clippy::struct_excessive_bools)] // One flag per marker attribute
 struct StructField {
     alias: Option<String>,
+    item_name: Option<String>,
+    any: bool,
     attribute: bool,
-    case: Option<String>,
+    attributes: bool,
+    case: Option<CaseArg>,
+    element: bool,
+    flatten: bool,
+    map: Option<MapArgs>,
     name: String,
-    namespace: Option<String>,
-    ty: Option<TypePath>,
+    namespace: Option<NamespaceRef>,
+    skip: SkipRules,
+    text: bool,
+    sequence: Option<Sequence>,
     unserialized: bool,
-    with: Option<Ident>,
+    wrap: Option<WrapArgs>,
+    with: Option<WithArg>,
+}
+
+impl StructField {
+    /// The builder call adding the element field `self.#member`, unless `skip` holds.
+    fn node_tokens(
+        &self,
+        reference: &TokenStream,
+        items: &TokenStream,
+        skip: Option<TokenStream>,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> TokenStream {
+        // `#[item_name]` names the items of a list, as `#[name]` does when there's no wrapper.
+        let alias = self
+            .item_name
+            .as_ref()
+            .or(self.alias.as_ref())
+            .map(|alias| quote! { .name(#alias) });
+        // An implicit element takes the case, leaving the value's own names alone.
+        let node_case = self.case.as_ref().filter(|_| !self.element).map(|case| {
+            let case = case.to_case_tokens();
+            quote! { .case(#case) }
+        });
+        let namespace_stream = self
+            .namespace
+            .as_ref()
+            .map(|ns| xml_attributes.namespace_tokens(ns, errors));
+        let with = self.with.as_ref();
+
+        if let Some(sequence) = self.sequence {
+            let converted = convert_tokens(with, &quote! { d });
+            // `#[any]` elements keep the names and namespaces they were built with.
+            let item = if self.any {
+                converted
+            } else {
+                quote! { #converted #alias #node_case #namespace_stream }
+            };
+            let items = sequence.items_tokens(items, &item);
+            if let Some(wrap) = &self.wrap {
+                let wrapper = self.wrapper_tokens(
+                    wrap,
+                    &quote! { .data(items.as_slice()) },
+                    &quote! { items.is_empty() },
+                    xml_attributes,
+                    errors,
+                );
+                let datum = skip_tokens(
+                    skip,
+                    quote! {{
+                        let items = #items;
+                        #wrapper
+                    }},
+                    &quote! { flexml::XML::None },
+                );
+                return quote! {
+                    .datum(#datum)
+                };
+            }
+
+            let items = skip_tokens(skip, items, &quote! { ::std::vec::Vec::new() });
+            return quote! {
+                .data((#items).as_slice())
+            };
+        }
+
+        let converted = convert_tokens(with, reference);
+        if self.flatten {
+            let converted = skip_tokens(skip, converted, &quote! { flexml::XML::None });
+            return quote! {
+                .merge(#converted)
+            };
+        }
+
+        // Map entries are named by their shape, so only a namespace carries over to them.
+        let mut value = self.map.as_ref().map_or_else(
+            || quote! { #converted #alias #node_case #namespace_stream },
+            |map| {
+                let shape = map.to_shape_tokens();
+                quote! { flexml::XMLMap::try_to_xml_map(#items, #shape)? #namespace_stream }
+            },
+        );
+        if let Some(wrap) = &self.wrap {
+            let wrapper = self.wrapper_tokens(
+                wrap,
+                &quote! { .datum(value) },
+                &quote! {
+                    match &value {
+                        flexml::XML::None => true,
+                        flexml::XML::Container(data) => data.is_empty(),
+                        _ => false,
+                    }
+                },
+                xml_attributes,
+                errors,
+            );
+            value = quote! {{
+                let value = #value;
+                #wrapper
+            }};
+        }
+        let datum = skip_tokens(skip, value, &quote! { flexml::XML::None });
+        quote! {
+            .datum(#datum)
+        }
+    }
+
+    /// Wraps the field in an element named after it, the way the named fields of enum variants
+    /// are, unless it has a `#[wrap]` of its own. Its `#[name]` and `#[namespace]` go to that
+    /// element.
+    fn wrap_as_element(&mut self) {
+        self.element = true;
+        if self.wrap.is_none() {
+            self.wrap = Some(WrapArgs {
+                name: self
+                    .alias
+                    .take()
+                    .map(|alias| LitStr::new(&alias, Span::call_site())),
+                namespace: self.namespace.take(),
+                skip_empty: false,
+            });
+        }
+    }
+
+    /// The `#[wrap]` element around a field, with its contents added by `add`. `is_empty` decides
+    /// whether the wrapper is left out for `skip_empty`.
+    fn wrapper_tokens(
+        &self,
+        wrap: &WrapArgs,
+        add: &TokenStream,
+        is_empty: &TokenStream,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> TokenStream {
+        let name = match (&wrap.name, &self.case) {
+            (Some(name), _) => quote! { #name },
+            (None, Some(case)) => case.apply(&self.name),
+            (None, None) => {
+                let name = &self.name;
+                quote! { #name }
+            }
+        };
+        let namespace_stream = wrap
+            .namespace
+            .as_ref()
+            .map(|ns| xml_attributes.namespace_tokens(ns, errors));
+        let wrapper = quote! { flexml::XML::new(#name) #namespace_stream #add };
+
+        if wrap.skip_empty {
+            quote! {
+                if #is_empty {
+                    flexml::XML::None
+                } else {
+                    #wrapper
+                }
+            }
+        } else {
+            wrapper
+        }
+    }
+
+    /// The `Option<XMLAttribute>` for the `#[attribute]` field behind `reference`.
+    fn attribute_tokens(
+        &self,
+        reference: &TokenStream,
+        xml_attributes: &XMLAttributes,
+        errors: &mut Errors,
+    ) -> TokenStream {
+        let key = match (&self.alias, &self.case) {
+            (Some(alias), _) => quote! { #alias },
+            (None, Some(case)) => case.apply(&self.name),
+            _ => {
+                let name = &self.name;
+                quote! { #name }
+            }
+        };
+        let namespace_stream = self
+            .namespace
+            .as_ref()
+            .map(|ns| xml_attributes.namespace_tokens(ns, errors));
+        let value = self.with.as_ref().map_or_else(
+            || reference.clone(),
+            |with| {
+                let formatted = with.convert(reference);
+                quote! { &#formatted }
+            },
+        );
+
+        attribute_tokens(&key, &value, namespace_stream)
+    }
 }
 
 impl From<DeriveAttributes> for StructField {
     fn from(value: DeriveAttributes) -> Self {
         Self {
             alias: value.alias,
+            item_name: value.item_name,
+            any: value.any,
             attribute: value.attribute,
+            // A catch-all map or list of attributes is a group like any other.
+            attributes: value.attributes || value.any_attributes,
             case: value.case,
+            element: false,
+            flatten: value.flatten,
+            map: value.map,
             name: String::new(),
             namespace: value.namespace,
-            ty: None,
+            skip: value.skip,
+            text: value.text,
+            sequence: (value.sequence || value.any).then_some(Sequence::Items),
             unserialized: value.unserialized,
+            wrap: value.wrap,
             with: value.with,
         }
     }
@@ -0,0 +1,118 @@
+use flexml::macros::ToXML;
+use flexml::{AsyncXMLWriter, IntoXML, TryIntoXML, XMLError, XML};
+use futures_util::stream;
+
+#[derive(ToXML)]
+#[namespaces(("AsyncNs", "https://async.com/namespace"))]
+#[namespace("AsyncNs")]
+struct Item {
+    #[attribute]
+    id: u32,
+    value: String,
+}
+
+#[tokio::test]
+async fn write_to_async() {
+    let xml = XML::new("Root")
+        .node(XML::new("Child").text(&"text".to_string()))
+        .node(XML::new("Empty"));
+
+    let mut out = Vec::new();
+    xml.write_to_async(&mut out)
+        .await
+        .expect("Failed to write xml");
+
+    assert_eq!(
+        xml.to_string(),
+        String::from_utf8(out).expect("Output is not utf-8")
+    );
+}
+
+#[tokio::test]
+async fn write_stream() {
+    let items = (0..3).map(|id| Item {
+        id,
+        value: format!("Item {id}"),
+    });
+
+    let mut writer = AsyncXMLWriter::new(Vec::new());
+    writer
+        .write_stream(&XML::new("Items"), stream::iter(items))
+        .await
+        .expect("Failed to write stream");
+
+    assert_eq!(
+        r#"<Items><a:Item id="0" xmlns:a="https://async.com/namespace">Item 0</a:Item><a:Item id="1" xmlns:a="https://async.com/namespace">Item 1</a:Item><a:Item id="2" xmlns:a="https://async.com/namespace">Item 2</a:Item></Items>"#,
+        String::from_utf8(writer.into_inner()).expect("Output is not utf-8")
+    );
+}
+
+#[tokio::test]
+async fn write_stream_declared_by_root() {
+    let item = Item {
+        id: 0,
+        value: "Item 0".to_string(),
+    };
+    // Converting the item registers AsyncNs before the root uses it.
+    let child = item.to_xml();
+    let root = XML::new("Items")
+        .namespace("AsyncNs")
+        .expect("Failed to set namespace")
+        .node(child);
+
+    let mut writer = AsyncXMLWriter::new(Vec::new());
+    writer
+        .write_stream(&root, stream::iter([item]))
+        .await
+        .expect("Failed to write stream");
+
+    assert_eq!(
+        r#"<a:Items xmlns:a="https://async.com/namespace"><a:Item id="0">Item 0</a:Item><a:Item id="0">Item 0</a:Item></a:Items>"#,
+        String::from_utf8(writer.into_inner()).expect("Output is not utf-8")
+    );
+}
+
+#[tokio::test]
+async fn write_stream_edge_cases() {
+    let mut writer = AsyncXMLWriter::new(Vec::new());
+    writer
+        .write_stream(&XML::new("Items"), stream::iter(Vec::<Item>::new()))
+        .await
+        .expect("Failed to write stream");
+    assert_eq!(
+        XML::new("Items").to_string(),
+        String::from_utf8(writer.into_inner()).expect("Output is not utf-8")
+    );
+
+    let declared = XML::new("Item").declaration(flexml::XMLDeclaration::default());
+    let mut writer = AsyncXMLWriter::new(Vec::new());
+    let error = writer
+        .write_stream(&XML::new("Items"), stream::iter([declared]))
+        .await
+        .expect_err("Nested declarations should be rejected");
+    assert_eq!(std::io::ErrorKind::InvalidInput, error.kind());
+}
+
+struct Broken;
+
+impl TryIntoXML for Broken {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        Err(XMLError::Other("Broken".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn conversion_errors() {
+    let mut writer = AsyncXMLWriter::new(Vec::new());
+    let error = writer
+        .write(&Broken)
+        .await
+        .expect_err("Conversion errors should be returned");
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+
+    let error = writer
+        .write_stream(&XML::new("Items"), stream::iter([Broken]))
+        .await
+        .expect_err("Conversion errors should be returned");
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+}
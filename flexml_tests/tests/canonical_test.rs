@@ -0,0 +1,57 @@
+use flexml::{Canonicalization, XMLAttribute, XMLDeclaration, XMLEncoding, XMLNamespaces, XML};
+
+fn document() -> XML {
+    XMLNamespaces::insert("Canonical", "https://canonical.com/namespace")
+        .expect("failed to insert namespace");
+    XMLNamespaces::insert("Example", "https://example.com/namespace")
+        .expect("failed to insert namespace");
+
+    XML::new("Root")
+        .namespace("Canonical")
+        .expect("Failed to set namespace")
+        .attribute(XMLAttribute::new("b", &"line\nbreak"))
+        .attribute(
+            XMLAttribute::new("a", &"namespaced")
+                .namespace("Example")
+                .expect("Failed to set namespace"),
+        )
+        .attribute(XMLAttribute::new("a", &"\"quoted\""))
+        .node(
+            XML::new("Child")
+                .namespace("Example")
+                .expect("Failed to set namespace")
+                .node(XML::new("Empty")),
+        )
+        .node(XML::new("Text").text(&"a < b & c".to_string()))
+        .declaration(XMLDeclaration::new((1, 0), XMLEncoding::UTF8))
+}
+
+#[test]
+fn c14n() {
+    assert_eq!(
+        r#"<c:Root xmlns:c="https://canonical.com/namespace" xmlns:e="https://example.com/namespace" a="&quot;quoted&quot;" b="line&#xA;break" e:a="namespaced"><e:Child><Empty></Empty></e:Child><Text>a &lt; b &amp; c</Text></c:Root>"#,
+        document().to_canonical_string(Canonicalization::C14N)
+    );
+}
+
+#[test]
+fn exclusive_c14n() {
+    assert_eq!(
+        r#"<c:Root xmlns:c="https://canonical.com/namespace" xmlns:e="https://example.com/namespace" a="&quot;quoted&quot;" b="line&#xA;break" e:a="namespaced"><e:Child><Empty></Empty></e:Child><Text>a &lt; b &amp; c</Text></c:Root>"#,
+        document().to_canonical_string(Canonicalization::ExclusiveC14N)
+    );
+
+    let wrapper = XML::new("Wrapper").node(
+        XML::new("Child")
+            .namespace("Example")
+            .expect("Failed to set namespace"),
+    );
+    assert_eq!(
+        r#"<Wrapper xmlns:e="https://example.com/namespace"><e:Child></e:Child></Wrapper>"#,
+        wrapper.to_canonical_string(Canonicalization::C14N)
+    );
+    assert_eq!(
+        r#"<Wrapper><e:Child xmlns:e="https://example.com/namespace"></e:Child></Wrapper>"#,
+        wrapper.to_canonical_string(Canonicalization::ExclusiveC14N)
+    );
+}
@@ -0,0 +1,95 @@
+use flexml::{
+    assert_xml_eq, xml_diff, AsXML, XMLAttribute, XMLDiffOptions, XMLDifference, XMLDifferenceKind,
+    XMLNamespaces, XML,
+};
+
+fn parse(markup: &str) -> XML {
+    markup.as_xml().expect("Failed to parse xml").into_owned()
+}
+
+fn document() -> XML {
+    XMLNamespaces::insert("DiffNs", "https://diff.com/namespace")
+        .expect("failed to insert namespace");
+
+    XML::new("root")
+        .namespace("DiffNs")
+        .expect("Failed to set namespace")
+        .attribute(XMLAttribute::new("a", &1))
+        .attribute(XMLAttribute::new("b", &2))
+        .node(XML::new("child").text(&"text".to_string()))
+        .node(XML::new("child"))
+}
+
+#[test]
+fn semantic_eq_ignores_order_and_prefix() {
+    let parsed = parse(
+        r#"<?xml version="1.0"?><other:root b="2" a="1" xmlns:other="https://diff.com/namespace"><child>te<![CDATA[xt]]></child><child></child></other:root>"#,
+    );
+
+    assert!(document().semantic_eq(&parsed));
+    assert_xml_eq!(document(), parsed);
+}
+
+#[test]
+fn semantic_eq_whitespace() {
+    let indented = r#"
+        <d:root a="1" b="2" xmlns:d="https://diff.com/namespace">
+            <child> text </child>
+            <child/>
+        </d:root>"#;
+
+    assert!(!document().semantic_eq(&parse(indented)));
+    assert_xml_eq!(
+        indented,
+        document(),
+        XMLDiffOptions::default().ignore_whitespace(true)
+    );
+}
+
+#[test]
+fn diff_paths() {
+    let other = parse(r#"<root a="1" c="3"><child>other</child><child/><extra/></root>"#);
+
+    assert_eq!(
+        vec![
+            XMLDifference {
+                path: "/root[1]".to_string(),
+                kind: XMLDifferenceKind::Namespace {
+                    left: Some("https://diff.com/namespace".to_string()),
+                    right: None,
+                },
+            },
+            XMLDifference {
+                path: "/root[1]".to_string(),
+                kind: XMLDifferenceKind::AttributeOnlyInLeft("b".to_string()),
+            },
+            XMLDifference {
+                path: "/root[1]".to_string(),
+                kind: XMLDifferenceKind::AttributeOnlyInRight("c".to_string()),
+            },
+            XMLDifference {
+                path: "/root[1]/extra[1]".to_string(),
+                kind: XMLDifferenceKind::ChildOnlyInRight("element <extra>".to_string()),
+            },
+            XMLDifference {
+                path: "/root[1]/child[1]/text()".to_string(),
+                kind: XMLDifferenceKind::Text {
+                    left: "text".to_string(),
+                    right: "other".to_string(),
+                },
+            },
+        ],
+        xml_diff(&document(), &other)
+    );
+}
+
+#[test]
+fn diff_path_of_right_only_child() {
+    assert_eq!(
+        vec![XMLDifference {
+            path: "/root[1]/b[2]".to_string(),
+            kind: XMLDifferenceKind::ChildOnlyInRight("element <b>".to_string()),
+        }],
+        xml_diff(&parse("<root><b/></root>"), &parse("<root><b/><b/></root>"))
+    );
+}
@@ -1,7 +1,12 @@
 use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet};
+use std::net::Ipv4Addr;
 
-use flexml::macros::ToXML;
-use flexml::{IntoXML, XMLAttribute, XML};
+use flexml::macros::{ToXML, ToXMLAttributeValue, ToXMLAttributes};
+use flexml::{
+    assert_xml_eq, Case, IntoXML, ToXMLAttributeValue, TryIntoXML, XMLAttribute, XMLError,
+    XMLNamespaceDef, XML,
+};
 
 #[derive(ToXML, Debug)]
 #[name("root")] // To manually name
@@ -125,13 +130,16 @@ fn unit_struct_unit_repr() {
 
 #[test]
 fn unit_struct_namespace() {
+    const NAMESPACE1: XMLNamespaceDef =
+        XMLNamespaceDef::new("Namespace1", "https://namespace1.com/namespace");
+
     #[derive(ToXML)]
     struct Root {
         data: Node,
     }
 
     #[derive(ToXML)]
-    #[namespace("Namespace1")]
+    #[namespace(NAMESPACE1)]
     struct Node;
 
     let test_struct = Root { data: Node {} };
@@ -156,6 +164,7 @@ enum NestedEnum {
 }
 
 #[derive(ToXML)]
+#[namespaces(("options_namespace", "https://options_namespace.com/namespace"))]
 #[unit_repr(true)]
 enum TaggedOptions {
     OneNamespacedSub(
@@ -477,3 +486,1196 @@ fn cow_node() {
 //             .datum(self.value2.to_xml())
 //     }
 // }
+
+#[derive(ToXML)]
+struct Page<T> {
+    #[attribute]
+    number: u32,
+    items: Vec<T>,
+}
+
+#[derive(ToXML)]
+#[name("Grid")]
+struct ConstGrid<'a, T, const N: usize>
+where
+    T: Copy,
+{
+    cells: &'a [T],
+}
+
+#[derive(ToXML)]
+#[bound(T: flexml::ToXMLAttributeValue)]
+struct AttributeOnly<T> {
+    #[attribute]
+    value: T,
+}
+
+#[test]
+fn generic_struct() {
+    let page = Page {
+        number: 1,
+        items: vec![
+            TaggedOptionsNodeB { data: 1 },
+            TaggedOptionsNodeB { data: 2 },
+        ],
+    };
+
+    assert_eq!(
+        r#"<Page number="1"><NodeB><Data>1</Data></NodeB><NodeB><Data>2</Data></NodeB></Page>"#,
+        page.to_xml().to_string()
+    );
+}
+
+#[test]
+fn generic_const_where() {
+    let grid = ConstGrid::<u8, 2> { cells: &[1, 2] };
+
+    assert_eq!("<Grid>12</Grid>", grid.to_xml().to_string());
+}
+
+#[test]
+fn generic_bound_override() {
+    struct NotXML;
+
+    impl ToXMLAttributeValue for NotXML {
+        fn to_xml_attribute_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed("rendered"))
+        }
+    }
+
+    assert_eq!(
+        r#"<AttributeOnly value="rendered"/>"#,
+        AttributeOnly { value: NotXML }.to_xml().to_string()
+    );
+}
+
+fn keep_acronyms(name: &str) -> String {
+    name.split('_')
+        .map(|part| match part {
+            "xml" | "id" => part.to_uppercase(),
+            _ => part[..1].to_uppercase() + &part[1..],
+        })
+        .collect()
+}
+
+#[derive(ToXML)]
+#[allow(non_camel_case_types)]
+#[case(keep_acronyms)]
+struct xml_record {
+    #[attribute]
+    #[case(keep_acronyms)]
+    record_id: u8,
+    #[case(|name: &str| name.to_lowercase())]
+    lower_child: TaggedOptionsNodeB,
+    #[case("kebab-case")]
+    kebab_child: TaggedOptionsNodeB,
+}
+
+#[test]
+fn custom_case() {
+    let record = xml_record {
+        record_id: 1,
+        lower_child: TaggedOptionsNodeB { data: 2 },
+        kebab_child: TaggedOptionsNodeB { data: 3 },
+    };
+
+    assert_eq!(
+        r#"<XMLRecord RecordID="1"><nodeb><Data>2</Data></nodeb><node-b><Data>3</Data></node-b></XMLRecord>"#,
+        record.to_xml().to_string()
+    );
+}
+
+#[test]
+fn case_from_str() {
+    let case: Case = "snake_case".parse().expect("snake_case is a known case");
+    assert_eq!("<foo_bar/>", XML::new("FooBar").case(case).to_string());
+
+    assert!("Title Case".parse::<Case>().is_err());
+    assert!("NoSuchCase".parse::<Case>().is_err());
+}
+
+struct Unregistered;
+
+impl TryIntoXML for Unregistered {
+    fn try_to_xml(&self) -> Result<XML, XMLError> {
+        XML::new("Unregistered").namespace("NeverRegistered")
+    }
+}
+
+#[derive(ToXML)]
+struct Fallible {
+    child: Unregistered,
+    children: Vec<Unregistered>,
+}
+
+#[test]
+fn try_to_xml_propagates_errors() {
+    let fallible = Fallible {
+        child: Unregistered,
+        children: vec![Unregistered],
+    };
+
+    assert!(matches!(
+        fallible.try_to_xml(),
+        Err(XMLError::NamespaceNotFound(ns)) if ns == "NeverRegistered"
+    ));
+}
+
+/// Displayable, but with no `IntoXML` implementation.
+struct Cents(u32);
+
+impl std::fmt::Display for Cents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+#[derive(ToXML)]
+struct Price {
+    #[attribute]
+    currency: String,
+    #[text]
+    amount: Cents,
+}
+
+#[derive(ToXML)]
+enum Charge {
+    Fee(#[text] Cents),
+    Discount {
+        #[text]
+        amount: Cents,
+    },
+}
+
+#[test]
+fn text_field() {
+    let price = Price {
+        currency: "EUR".to_string(),
+        amount: Cents(1250),
+    };
+
+    assert_eq!(
+        r#"<Price currency="EUR">12.50</Price>"#,
+        price.to_xml().to_string()
+    );
+    assert_eq!(
+        "<Fee>0.99</Fee>",
+        Charge::Fee(Cents(99)).to_xml().to_string()
+    );
+    assert_eq!(
+        "<Discount>1.00</Discount>",
+        Charge::Discount { amount: Cents(100) }.to_xml().to_string()
+    );
+}
+
+#[derive(ToXML)]
+struct Audit {
+    #[attribute]
+    #[name("createdBy")]
+    created_by: &'static str,
+    #[attribute]
+    revision: u32,
+    #[name("Note")]
+    note: Option<&'static str>,
+}
+
+#[derive(ToXML)]
+struct Order {
+    #[attribute]
+    id: u32,
+    #[flatten]
+    audit: Audit,
+    #[name("Item")]
+    item: &'static str,
+}
+
+#[derive(ToXML)]
+enum Document {
+    Invoice(#[flatten] Audit, u32),
+}
+
+#[derive(ToXML)]
+#[untagged]
+enum Untagged {
+    Pair { left: u8, right: u8 },
+}
+
+#[test]
+fn flatten_field() {
+    let order = Order {
+        id: 7,
+        audit: Audit {
+            created_by: "admin",
+            revision: 3,
+            note: Some("rush"),
+        },
+        item: "widget",
+    };
+
+    assert_eq!(
+        r#"<Order id="7" createdBy="admin" revision="3"><Note>rush</Note><Item>widget</Item></Order>"#,
+        order.to_xml().to_string()
+    );
+
+    let invoice = Document::Invoice(
+        Audit {
+            created_by: "clerk",
+            revision: 1,
+            note: None,
+        },
+        42,
+    );
+    assert_eq!(
+        r#"<Invoice createdBy="clerk" revision="1">42</Invoice>"#,
+        invoice.to_xml().to_string()
+    );
+
+    assert_eq!(
+        "<left>1</left><right>2</right>",
+        Untagged::Pair { left: 1, right: 2 }.to_xml().to_string()
+    );
+}
+
+#[derive(ToXML)]
+struct Tagged {
+    #[attribute]
+    x: u8,
+    #[name("Tag")]
+    tag: &'static str,
+}
+
+#[derive(ToXML)]
+#[untagged]
+struct TagPair {
+    #[flatten]
+    first: Tagged,
+    #[flatten]
+    second: Tagged,
+}
+
+#[derive(ToXML)]
+#[name("P")]
+struct Paragraph {
+    #[flatten]
+    tags: TagPair,
+}
+
+#[test]
+fn flatten_untagged_members() {
+    let paragraph = Paragraph {
+        tags: TagPair {
+            first: Tagged { x: 1, tag: "a" },
+            second: Tagged { x: 2, tag: "b" },
+        },
+    };
+
+    // An untagged struct has no tag for the attributes of its members.
+    assert_eq!(
+        "<P><Tag>a</Tag><Tag>b</Tag></P>",
+        paragraph.to_xml().to_string()
+    );
+}
+
+#[test]
+fn merge() {
+    let merged = XML::new("Parent")
+        .attribute(XMLAttribute::new("a", &1))
+        .merge(
+            XML::new("Dropped")
+                .attribute(XMLAttribute::new("b", &2))
+                .datum(XML::new("Child")),
+        )
+        .merge(XML::Text("text".to_string()));
+
+    assert_eq!(
+        r#"<Parent a="1" b="2"><Child/>text</Parent>"#,
+        merged.to_string()
+    );
+
+    let merged = XML::new("Parent")
+        .attribute(XMLAttribute::new("a", &1))
+        .merge(XML::new("Dropped").attribute(XMLAttribute::new("a", &2)))
+        .merge(XML::Container(vec![
+            XML::new("First").attribute(XMLAttribute::new("b", &1)),
+            XML::new("Second"),
+        ]));
+
+    assert_eq!(
+        r#"<Parent a="2"><First b="1"/><Second/></Parent>"#,
+        merged.to_string()
+    );
+}
+
+#[derive(ToXMLAttributes)]
+#[namespaces(("XLink", "http://www.w3.org/1999/xlink"))]
+struct XLink {
+    #[namespace("XLink")]
+    href: &'static str,
+    #[name("type")]
+    #[namespace("XLink")]
+    kind: &'static str,
+}
+
+#[derive(ToXMLAttributes)]
+#[case_all("lowerCamelCase")]
+struct Stamp {
+    #[attributes]
+    link: XLink,
+    created_by: &'static str,
+    #[unserialized]
+    _cache: u8,
+}
+
+#[derive(ToXML)]
+struct Figure {
+    #[attributes]
+    stamp: Stamp,
+    #[attributes]
+    fallback: Option<XLink>,
+    #[name("Caption")]
+    caption: &'static str,
+}
+
+#[test]
+fn attribute_groups() {
+    let figure = Figure {
+        stamp: Stamp {
+            link: XLink {
+                href: "figure.png",
+                kind: "simple",
+            },
+            created_by: "editor",
+            _cache: 0,
+        },
+        fallback: None,
+        caption: "A figure",
+    };
+
+    assert_xml_eq!(
+        r#"<Figure xmlns:x="http://www.w3.org/1999/xlink" x:href="figure.png" x:type="simple" createdBy="editor"><Caption>A figure</Caption></Figure>"#,
+        figure.to_xml()
+    );
+}
+
+#[derive(ToXMLAttributeValue)]
+#[case_all("kebab-case")]
+enum Availability {
+    InStock,
+    #[name("backorder")]
+    OnBackorder,
+    Discontinued,
+}
+
+#[derive(ToXML)]
+struct Listing {
+    #[attribute]
+    availability: Availability,
+    #[attribute]
+    sizes: Vec<u8>,
+    #[attribute]
+    discount: Option<u8>,
+    #[attribute]
+    featured: bool,
+    #[attribute]
+    tags: Vec<Option<&'static str>>,
+}
+
+#[test]
+fn attribute_values() {
+    let listing = Listing {
+        availability: Availability::InStock,
+        sizes: vec![38, 40, 42],
+        discount: None,
+        featured: true,
+        tags: vec![Some("new"), None, Some("sale")],
+    };
+    assert_xml_eq!(
+        r#"<Listing availability="in-stock" sizes="38 40 42" featured="true" tags="new sale"/>"#,
+        listing.to_xml()
+    );
+
+    let listing = Listing {
+        availability: Availability::OnBackorder,
+        sizes: Vec::new(),
+        discount: Some(10),
+        featured: false,
+        tags: Vec::new(),
+    };
+    assert_xml_eq!(
+        r#"<Listing availability="backorder" discount="10" featured="false"/>"#,
+        listing.to_xml()
+    );
+    assert_eq!(
+        Some("discontinued"),
+        Availability::Discontinued
+            .to_xml_attribute_value()
+            .as_deref()
+    );
+}
+
+#[derive(ToXML)]
+struct Depot {
+    #[attribute]
+    doors: [u8; 2],
+    #[attribute]
+    regions: BTreeSet<&'static str>,
+    #[attribute]
+    codes: HashSet<u16>,
+    // Foreign types can't implement ToXMLAttributeValue, but Display still works
+    #[attribute(display)]
+    address: Ipv4Addr,
+}
+
+#[test]
+fn attribute_value_collections() {
+    let depot = Depot {
+        doors: [1, 2],
+        regions: BTreeSet::from(["north", "east"]),
+        codes: HashSet::from([7, 3]),
+        address: Ipv4Addr::new(10, 0, 0, 1),
+    };
+    assert_xml_eq!(
+        r#"<Depot doors="1 2" regions="east north" codes="3 7" address="10.0.0.1"/>"#,
+        depot.to_xml()
+    );
+
+    let depot = Depot {
+        doors: [1, 2],
+        regions: BTreeSet::new(),
+        codes: HashSet::new(),
+        address: Ipv4Addr::LOCALHOST,
+    };
+    assert_xml_eq!(
+        r#"<Depot doors="1 2" address="127.0.0.1"/>"#,
+        depot.to_xml()
+    );
+}
+
+fn iso_duration(duration: &std::time::Duration) -> String {
+    format!("PT{}S", duration.as_secs())
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // #[with] formatters take a reference
+fn nonzero(code: &u16) -> Option<u16> {
+    (*code != 0).then_some(*code)
+}
+
+mod money {
+    #[allow(clippy::trivially_copy_pass_by_ref)] // #[with] formatters take a reference
+    pub fn dollars(cents: &u64) -> String {
+        format!("{}.{:02}", cents / 100, cents % 100)
+    }
+}
+
+#[derive(ToXMLAttributes)]
+struct Billing {
+    #[with(money::dollars)]
+    total: u64,
+}
+
+#[derive(ToXML)]
+struct Call {
+    #[attribute(with = self::iso_duration)]
+    length: std::time::Duration,
+    #[attribute("UpperCamelCase", with = money::dollars)]
+    cost: u64,
+    #[attribute]
+    #[with(self::nonzero)]
+    error_code: u16,
+    // A lone identifier is a method, as it is on elements
+    #[attribute(with = to_uppercase)]
+    region: &'static str,
+    #[attributes]
+    billing: Billing,
+}
+
+#[test]
+fn attribute_formatters() {
+    let call = Call {
+        length: std::time::Duration::from_secs(95),
+        cost: 1205,
+        error_code: 0,
+        billing: Billing { total: 1_000_001 },
+        region: "emea",
+    };
+    assert_xml_eq!(
+        r#"<Call length="PT95S" Cost="12.05" region="EMEA" total="10000.01"/>"#,
+        call.to_xml()
+    );
+}
+
+struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+mod xml_conv {
+    use flexml::XML;
+
+    pub fn iso_date(date: &super::Date) -> XML {
+        XML::Text(format!(
+            "{:04}-{:02}-{:02}",
+            date.year, date.month, date.day
+        ))
+    }
+
+    pub fn coordinates(point: &super::Point) -> XML {
+        XML::new("Point").text(&format!("{} {}", point.lat, point.lon))
+    }
+
+    pub fn ordinal(event: &super::Event) -> XML {
+        let super::Event::Round(n) = event else {
+            return XML::None;
+        };
+        XML::new("Round").text(&format!("#{n}"))
+    }
+}
+
+#[derive(ToXML)]
+struct Schedule {
+    #[with(xml_conv::iso_date)]
+    #[name("Starts")]
+    starts: Date,
+    #[with(xml_conv::iso_date)]
+    #[name("Holiday")]
+    holidays: Vec<Date>,
+    #[with(|seats: &u16| XML::Text(format!("{seats} seats")))]
+    #[name("Capacity")]
+    capacity: u16,
+    #[attribute(with = |venue: &&str| venue.to_uppercase())]
+    venue: &'static str,
+    events: Vec<Event>,
+}
+
+#[derive(ToXML)]
+enum Event {
+    #[with(xml_conv::ordinal)]
+    Round(u8),
+    Talk(&'static str),
+}
+
+#[test]
+fn with_functions_and_closures() {
+    let schedule = Schedule {
+        starts: Date {
+            year: 2024,
+            month: 3,
+            day: 9,
+        },
+        holidays: vec![Date {
+            year: 2024,
+            month: 12,
+            day: 25,
+        }],
+        capacity: 40,
+        venue: "hall",
+        events: vec![Event::Round(1), Event::Talk("Keynote")],
+    };
+
+    assert_xml_eq!(
+        r#"<Schedule venue="HALL"><Starts>2024-03-09</Starts><Holiday>2024-12-25</Holiday><Capacity>40 seats</Capacity><Round>#1</Round><Talk>Keynote</Talk></Schedule>"#,
+        schedule.to_xml()
+    );
+}
+
+/// A type that kept its hand-written `IntoXML` impl instead of moving to `TryIntoXML`.
+struct LegacyNode(u8);
+
+impl IntoXML for LegacyNode {
+    fn to_xml(&self) -> XML {
+        XML::new("Legacy").text(&self.0.to_string())
+    }
+}
+
+#[derive(ToXML)]
+struct LegacyHolder {
+    #[with(to_xml)]
+    legacy: LegacyNode,
+}
+
+#[test]
+fn with_hand_written_into_xml() {
+    let holder = LegacyHolder {
+        legacy: LegacyNode(7),
+    };
+
+    assert_eq!(
+        "<LegacyHolder><Legacy>7</Legacy></LegacyHolder>",
+        holder.to_xml().to_string()
+    );
+}
+
+struct Money {
+    cents: i64,
+    currency: &'static str,
+}
+
+#[derive(ToXML)]
+#[name("Money")]
+struct MoneyXML {
+    #[attribute]
+    currency: &'static str,
+    #[text]
+    amount: String,
+}
+
+impl From<&Money> for MoneyXML {
+    fn from(money: &Money) -> Self {
+        Self {
+            currency: money.currency,
+            amount: format!("{}.{:02}", money.cents / 100, money.cents % 100),
+        }
+    }
+}
+
+#[derive(ToXML)]
+#[into(MoneyXML)]
+#[name("Total")]
+#[namespaces(("Finance", "https://finance.com/namespace"))]
+#[namespace("Finance")]
+struct Total(Money);
+
+impl From<&Total> for MoneyXML {
+    fn from(total: &Total) -> Self {
+        Self::from(&total.0)
+    }
+}
+
+#[derive(ToXML)]
+#[with(xml_conv::coordinates)]
+#[declaration]
+struct Point {
+    lat: f32,
+    lon: f32,
+}
+
+#[derive(ToXML)]
+#[with(as_rgb)]
+#[allow(dead_code)]
+enum Color {
+    Red,
+    Gray(u8),
+}
+
+impl Color {
+    fn as_rgb(&self) -> XML {
+        let (r, g, b) = match self {
+            Self::Red => (255, 0, 0),
+            Self::Gray(v) => (*v, *v, *v),
+        };
+        XML::new("Rgb").text(&format!("{r},{g},{b}"))
+    }
+}
+
+#[test]
+fn container_delegation() {
+    let total = Total(Money {
+        cents: 4250,
+        currency: "EUR",
+    });
+    assert_xml_eq!(
+        r#"<f:Total xmlns:f="https://finance.com/namespace" currency="EUR">42.50</f:Total>"#,
+        total.to_xml()
+    );
+
+    let point = Point {
+        lat: 51.5,
+        lon: -0.125,
+    };
+    assert_eq!(
+        r#"<?xml version="1.0" ?><Point>51.5 -0.125</Point>"#,
+        point.to_xml().to_string()
+    );
+
+    assert_xml_eq!("<Rgb>128,128,128</Rgb>", Color::Gray(128).to_xml());
+}
+
+#[derive(ToXML)]
+#[skip_none]
+enum Contact {
+    Person {
+        name: &'static str,
+        email: Option<&'static str>,
+        #[skip_if(str::is_empty)]
+        phone: &'static str,
+    },
+}
+
+#[derive(ToXML)]
+#[skip_default]
+struct Settings {
+    #[attribute]
+    retries: u8,
+    #[attribute]
+    #[skip_if(|v: &&str| v.len() > 8)]
+    mode: &'static str,
+    #[name("Timeout")]
+    timeout: u32,
+    #[text]
+    note: String,
+}
+
+#[derive(ToXMLAttributes)]
+struct Paging {
+    #[skip_default]
+    offset: u32,
+    #[skip_none]
+    limit: Option<u32>,
+}
+
+#[derive(ToXML)]
+struct Query {
+    #[attributes]
+    paging: Paging,
+    #[name("Term")]
+    #[skip_if(Vec::is_empty)]
+    terms: Vec<&'static str>,
+}
+
+#[test]
+fn skip_rules() {
+    let contact = Contact::Person {
+        name: "Ada",
+        email: None,
+        phone: "",
+    };
+    assert_xml_eq!("<Person><name>Ada</name></Person>", contact.to_xml());
+
+    let contact = Contact::Person {
+        name: "Ada",
+        email: Some("ada@example.com"),
+        phone: "555",
+    };
+    assert_xml_eq!(
+        "<Person><name>Ada</name><email>ada@example.com</email><phone>555</phone></Person>",
+        contact.to_xml()
+    );
+
+    let settings = Settings {
+        retries: 0,
+        mode: "fast",
+        timeout: 0,
+        note: String::new(),
+    };
+    assert_eq!(r#"<Settings mode="fast"/>"#, settings.to_xml().to_string());
+
+    let settings = Settings {
+        retries: 3,
+        mode: "exhaustive",
+        timeout: 30,
+        note: "tuned".to_string(),
+    };
+    assert_xml_eq!(
+        r#"<Settings retries="3"><Timeout>30</Timeout>tuned</Settings>"#,
+        settings.to_xml()
+    );
+
+    let query = Query {
+        paging: Paging {
+            offset: 0,
+            limit: None,
+        },
+        terms: Vec::new(),
+    };
+    assert_xml_eq!("<Query/>", query.to_xml());
+
+    let query = Query {
+        paging: Paging {
+            offset: 20,
+            limit: Some(10),
+        },
+        terms: vec!["xml"],
+    };
+    assert_xml_eq!(
+        r#"<Query offset="20" limit="10"><Term>xml</Term></Query>"#,
+        query.to_xml()
+    );
+}
+
+#[derive(ToXML)]
+#[name("Item")]
+struct CartItem {
+    #[attribute]
+    sku: &'static str,
+}
+
+#[derive(ToXML)]
+#[case_all("UpperCamelCase")]
+#[namespaces(("Cart", "https://cart.com/namespace"))]
+struct Cart {
+    #[wrap]
+    items: Vec<CartItem>,
+    #[wrap("Coupons", namespace = "Cart", skip_empty)]
+    #[item_name("Code")]
+    coupons: Vec<&'static str>,
+    #[wrap("Notes")]
+    #[item_name("Note")]
+    notes: Vec<&'static str>,
+    #[wrap("Gift", skip_empty)]
+    gift_message: Option<&'static str>,
+}
+
+#[test]
+fn wrapped_collections() {
+    let cart = Cart {
+        items: vec![CartItem { sku: "a-1" }, CartItem { sku: "b-2" }],
+        coupons: Vec::new(),
+        notes: Vec::new(),
+        gift_message: None,
+    };
+    assert_xml_eq!(
+        r#"<Cart><Items><Item sku="a-1"/><Item sku="b-2"/></Items><Notes/></Cart>"#,
+        cart.to_xml()
+    );
+
+    let cart = Cart {
+        items: Vec::new(),
+        coupons: vec!["SAVE10"],
+        notes: vec!["Leave at door"],
+        gift_message: Some("Happy birthday"),
+    };
+    assert_xml_eq!(
+        r#"<Cart xmlns:c="https://cart.com/namespace"><Items/><c:Coupons><Code>SAVE10</Code></c:Coupons><Notes><Note>Leave at door</Note></Notes><Gift>Happy birthday</Gift></Cart>"#,
+        cart.to_xml()
+    );
+}
+
+type Tags = std::collections::BTreeSet<&'static str>;
+
+struct Shelf(Vec<&'static str>);
+
+impl<'a> IntoIterator for &'a Shelf {
+    type Item = &'a &'static str;
+    type IntoIter = std::slice::Iter<'a, &'static str>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[derive(ToXML)]
+#[case_all("UpperCamelCase")]
+struct Library {
+    #[name("Queued")]
+    queue: std::collections::VecDeque<&'static str>,
+    #[name("Genre")]
+    genres: std::collections::BTreeSet<&'static str>,
+    #[name("Slot")]
+    slots: [u8; 2],
+    #[name("Archived")]
+    archive: Box<[&'static str]>,
+    #[name("Pinned")]
+    pinned: &'static [&'static str],
+    #[name("Shared")]
+    shared: std::rc::Rc<[&'static str]>,
+    #[name("Synced")]
+    synced: std::sync::Arc<[&'static str]>,
+    #[name("Author")]
+    authors: HashSet<&'static str>,
+    #[sequence]
+    #[name("Tag")]
+    tags: Tags,
+    #[sequence]
+    #[wrap]
+    #[item_name("Book")]
+    shelf: Shelf,
+}
+
+#[test]
+fn sequences() {
+    let library = Library {
+        queue: ["b", "a"].into(),
+        genres: ["poetry", "drama"].into(),
+        slots: [1, 2],
+        archive: Box::new(["old"]),
+        pinned: &["new"],
+        shared: ["lent"].into(),
+        synced: ["mirrored"].into(),
+        authors: ["Woolf", "Austen", "Herbert"].into(),
+        tags: ["rare"].into(),
+        shelf: Shelf(vec!["Dune", "Emma"]),
+    };
+
+    assert_xml_eq!(
+        "<Library><Queued>b</Queued><Queued>a</Queued><Genre>drama</Genre><Genre>poetry</Genre><Slot>1</Slot><Slot>2</Slot><Archived>old</Archived><Pinned>new</Pinned><Shared>lent</Shared><Synced>mirrored</Synced><Author>Austen</Author><Author>Herbert</Author><Author>Woolf</Author><Tag>rare</Tag><Shelf><Book>Dune</Book><Book>Emma</Book></Shelf></Library>",
+        library.to_xml()
+    );
+}
+
+#[derive(ToXML)]
+#[namespaces(("Meta", "https://meta.com/namespace"))]
+struct Payload {
+    #[wrap("Settings")]
+    settings: std::collections::BTreeMap<&'static str, u32>,
+    #[map(key_name)]
+    #[wrap("Labels", skip_empty)]
+    labels: std::collections::HashMap<String, &'static str>,
+    #[map(nested, entry = "Pair", key = "Name", value = "Data")]
+    #[namespace("Meta")]
+    metadata: indexmap::IndexMap<&'static str, &'static str>,
+    #[map(entry = "Limit", key = "for")]
+    #[skip_if(std::collections::BTreeMap::is_empty)]
+    limits: std::collections::BTreeMap<&'static str, u8>,
+}
+
+#[test]
+fn maps() {
+    let mut payload = Payload {
+        settings: [("volume", 7), ("brightness", 3)].into(),
+        labels: [("zone".to_string(), "eu"), ("tier".to_string(), "gold")].into(),
+        metadata: [("version", "2"), ("author", "kim")].into(),
+        limits: std::collections::BTreeMap::new(),
+    };
+    assert_xml_eq!(
+        r#"<Payload xmlns:m="https://meta.com/namespace"><Settings><entry key="brightness">3</entry><entry key="volume">7</entry></Settings><Labels><tier>gold</tier><zone>eu</zone></Labels><m:Pair><Name>version</Name><Data>2</Data></m:Pair><m:Pair><Name>author</Name><Data>kim</Data></m:Pair></Payload>"#,
+        payload.to_xml()
+    );
+
+    payload.settings.clear();
+    payload.labels.clear();
+    payload.metadata.clear();
+    payload.limits.insert("uploads", 5);
+    assert_xml_eq!(
+        r#"<Payload><Settings/><Limit for="uploads">5</Limit></Payload>"#,
+        payload.to_xml()
+    );
+
+    payload.labels.insert("not a name".to_string(), "x");
+    assert!(matches!(
+        payload.try_to_xml(),
+        Err(XMLError::Name(name)) if name == "not a name"
+    ));
+
+    let hash_map: std::collections::HashMap<u8, bool> = [(2, false), (1, true)].into();
+    assert_eq!(
+        r#"<entry key="1">true</entry><entry key="2">false</entry>"#,
+        hash_map.to_xml().to_string()
+    );
+}
+
+const VENDOR: XMLNamespaceDef = XMLNamespaceDef::new("Vendor", "https://vendor.com/namespace");
+
+#[derive(ToXMLAttributes)]
+struct Provenance {
+    source: &'static str,
+    #[any_attributes]
+    extra: std::collections::BTreeMap<&'static str, Option<u32>>,
+}
+
+#[derive(ToXML)]
+#[case_all("lowerCamelCase")]
+struct Offer {
+    #[attribute]
+    sku: u32,
+    #[any_attributes]
+    custom: std::collections::HashMap<String, String>,
+    #[any_attributes]
+    signed: Vec<XMLAttribute>,
+    #[attributes]
+    provenance: Provenance,
+    #[name("Title")]
+    title: &'static str,
+    #[any]
+    extensions: Vec<XML>,
+    #[any]
+    #[wrap("Extras", skip_empty)]
+    extras: Vec<XML>,
+}
+
+#[test]
+fn catch_alls() {
+    let rating = XML::new("Rating")
+        .namespace(VENDOR.register().expect("failed to insert namespace"))
+        .expect("Failed to set namespace")
+        .text("5");
+    let mut offer = Offer {
+        sku: 7,
+        custom: [
+            ("zone".to_string(), "eu".to_string()),
+            ("batch".to_string(), "b-12".to_string()),
+        ]
+        .into(),
+        signed: vec![XMLAttribute::new("sig", &"abc")],
+        provenance: Provenance {
+            source: "feed",
+            extra: [("rank", Some(2)), ("score", None)].into(),
+        },
+        title: "Chair",
+        extensions: vec![rating, XML::new("Legacy_Code").text("L-1")],
+        extras: Vec::new(),
+    };
+    assert_xml_eq!(
+        r#"<Offer sku="7" batch="b-12" zone="eu" sig="abc" source="feed" rank="2" xmlns:v="https://vendor.com/namespace"><title>Chair</title><v:Rating>5</v:Rating><Legacy_Code>L-1</Legacy_Code></Offer>"#,
+        offer.to_xml()
+    );
+
+    offer.extras.push(XML::new("Note"));
+    offer.custom.insert("bad key".to_string(), String::new());
+    assert!(matches!(
+        offer.try_to_xml(),
+        Err(XMLError::Name(name)) if name == "bad key"
+    ));
+    offer.custom.clear();
+    assert_xml_eq!(
+        r#"<Offer sku="7" sig="abc" source="feed" rank="2" xmlns:v="https://vendor.com/namespace"><title>Chair</title><v:Rating>5</v:Rating><Legacy_Code>L-1</Legacy_Code><Extras><Note/></Extras></Offer>"#,
+        offer.to_xml()
+    );
+}
+
+#[derive(ToXML)]
+#[tag_attribute("type")]
+#[case_all("snake_case")]
+enum Shape {
+    Circle {
+        radius: u8,
+    },
+    Square {
+        #[attribute]
+        side: u8,
+    },
+    #[name("text")]
+    Label(#[text] &'static str),
+    Empty,
+}
+
+#[derive(ToXML)]
+#[name("Payment")]
+#[tag_attribute(xsi)]
+enum PaymentMethod {
+    #[name("CardPayment")]
+    Card {
+        #[name("Number")]
+        number: &'static str,
+    },
+    Cash,
+}
+
+#[derive(ToXML)]
+struct Drawing {
+    shapes: Vec<Shape>,
+    payment: PaymentMethod,
+}
+
+#[test]
+fn tag_attributes() {
+    let drawing = Drawing {
+        shapes: vec![
+            Shape::Circle { radius: 2 },
+            Shape::Square { side: 3 },
+            Shape::Label("Hi"),
+            Shape::Empty,
+        ],
+        payment: PaymentMethod::Card { number: "4111" },
+    };
+    assert_xml_eq!(
+        r#"<Drawing xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><Shape type="circle"><radius>2</radius></Shape><Shape type="square" side="3"/><Shape type="text">Hi</Shape><Shape type="empty"/><Payment xsi:type="CardPayment"><Number>4111</Number></Payment></Drawing>"#,
+        drawing.to_xml()
+    );
+    assert_xml_eq!(
+        r#"<Payment xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Cash"/>"#,
+        PaymentMethod::Cash.to_xml()
+    );
+}
+
+#[derive(ToXML)]
+enum Message {
+    Ping {
+        #[attribute]
+        id: u32,
+        #[unserialized]
+        _sent_at: u64,
+    },
+    Chat {
+        #[attribute]
+        id: u32,
+        #[skip_none]
+        #[name("Subject")]
+        subject: Option<&'static str>,
+        #[item_name("Line")]
+        lines: Vec<&'static str>,
+        #[case("UpperCamelCase")]
+        #[with(|s: &&str| s.to_uppercase())]
+        shout: &'static str,
+    },
+    Receipt(
+        #[attribute]
+        #[name("id")]
+        u32,
+        #[text] &'static str,
+    ),
+}
+
+#[test]
+fn variant_fields() {
+    let messages = [
+        Message::Ping {
+            id: 1,
+            _sent_at: 1_700_000_000,
+        },
+        Message::Chat {
+            id: 2,
+            subject: None,
+            lines: vec!["hi", "there"],
+            shout: "hey",
+        },
+        Message::Receipt(3, "ok"),
+    ];
+
+    assert_eq!(
+        r#"<Ping id="1"/><Chat id="2"><lines><Line>hi</Line><Line>there</Line></lines><Shout>HEY</Shout></Chat><Receipt id="3">ok</Receipt>"#,
+        messages.as_slice().to_xml().to_string()
+    );
+}
+
+// #[text] enums are attribute values too, without deriving ToXMLAttributeValue
+#[derive(ToXML)]
+#[text]
+#[case_all("lowerCamelCase")]
+enum Hue {
+    Red,
+    DeepBlue,
+    #[name("grey")]
+    Gray,
+}
+
+#[derive(ToXML)]
+#[unit_repr(0)]
+enum Signal {
+    Off,
+    #[unit_repr(1)]
+    On,
+    #[unit_repr("?")]
+    Unknown,
+    Level(u8),
+}
+
+#[derive(ToXML)]
+enum Never {}
+
+#[derive(ToXML)]
+struct Palette {
+    #[attribute]
+    accent: Hue,
+    #[name("Primary")]
+    primary: Hue,
+    fill: Hue,
+    signals: Vec<Signal>,
+    never: Option<Never>,
+}
+
+#[test]
+fn unit_variants() {
+    let palette = Palette {
+        accent: Hue::DeepBlue,
+        primary: Hue::Red,
+        fill: Hue::Gray,
+        signals: vec![Signal::Off, Signal::On, Signal::Unknown, Signal::Level(4)],
+        never: None,
+    };
+
+    assert_xml_eq!(
+        r#"<Palette accent="deepBlue"><Primary>red</Primary>grey<Off>0</Off><On>1</On><Unknown>?</Unknown><Level>4</Level></Palette>"#,
+        palette.to_xml()
+    );
+}
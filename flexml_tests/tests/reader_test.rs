@@ -0,0 +1,87 @@
+use std::io::Read;
+
+use flexml::macros::ToXML;
+use flexml::{IntoXML, XML};
+
+#[derive(ToXML)]
+struct Document {
+    #[attribute]
+    id: u32,
+    entries: Vec<Entry>,
+}
+
+#[derive(ToXML)]
+struct Entry {
+    #[attribute]
+    index: usize,
+    text: String,
+}
+
+fn document() -> Document {
+    Document {
+        id: 7,
+        entries: (0..32)
+            .map(|index| Entry {
+                index,
+                text: format!("Entry number {index}"),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn read_to_string() {
+    let xml = document().to_xml();
+
+    let mut out = String::new();
+    xml.reader()
+        .read_to_string(&mut out)
+        .expect("Failed to read xml");
+
+    assert_eq!(xml.to_string(), out);
+}
+
+#[test]
+fn read_in_small_buffers() {
+    let xml = document().to_xml();
+    let mut reader = xml.reader();
+
+    let mut out = Vec::new();
+    let mut buf = [0; 7];
+    loop {
+        let len = reader.read(&mut buf).expect("Failed to read xml");
+        if len == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..len]);
+    }
+
+    assert_eq!(
+        xml.to_string(),
+        String::from_utf8(out).expect("Output is not utf-8")
+    );
+}
+
+#[test]
+fn read_fragment_by_fragment() {
+    let xml = document().to_xml();
+
+    // The reader never holds more than one of these at a time.
+    let longest = xml
+        .chunks()
+        .map(|chunk| chunk.len())
+        .max()
+        .unwrap_or_default();
+    assert!(longest <= r#"<Entry index="31">"#.len());
+    assert!(xml.to_string().len() > 32 * longest);
+}
+
+#[test]
+fn copy_owned_reader() {
+    let xml = XML::new("Root").node(XML::new("Empty"));
+
+    let mut out = Vec::new();
+    std::io::copy(&mut xml.into_reader(), &mut out).expect("Failed to copy xml");
+
+    assert_eq!(b"<Root><Empty/></Root>", out.as_slice());
+}
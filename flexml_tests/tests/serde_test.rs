@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use flexml::{assert_xml_eq, XMLError, XMLNamespaces};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename = "SerdeNs:Order")]
+struct Order {
+    #[serde(rename = "@id")]
+    id: u32,
+    #[serde(rename = "@tags")]
+    tags: Vec<&'static str>,
+    customer: Customer,
+    #[serde(rename = "line")]
+    lines: Vec<Line>,
+    note: Option<String>,
+    status: Status,
+    metadata: BTreeMap<&'static str, u32>,
+}
+
+#[derive(Serialize)]
+struct Customer {
+    #[serde(rename = "@SerdeNs:vip")]
+    vip: bool,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct Line {
+    #[serde(rename = "@sku")]
+    sku: &'static str,
+    #[serde(rename = "$value")]
+    quantity: u16,
+}
+
+#[derive(Serialize)]
+enum Status {
+    Shipped { carrier: &'static str },
+}
+
+#[test]
+fn serialize_struct() {
+    XMLNamespaces::insert("SerdeNs", "https://serde.com/namespace")
+        .expect("failed to insert namespace");
+
+    let order = Order {
+        id: 4,
+        tags: vec!["rush", "gift"],
+        customer: Customer {
+            vip: true,
+            name: "Ada".to_string(),
+        },
+        lines: vec![
+            Line {
+                sku: "A-1",
+                quantity: 2,
+            },
+            Line {
+                sku: "B-2",
+                quantity: 1,
+            },
+        ],
+        note: None,
+        status: Status::Shipped { carrier: "Post" },
+        metadata: BTreeMap::from([("weight", 3), ("boxes", 1)]),
+    };
+
+    let xml = flexml::to_xml(&order).expect("Failed to serialize");
+
+    assert_xml_eq!(
+        r#"<s:Order id="4" tags="rush gift" xmlns:s="https://serde.com/namespace"><customer s:vip="true"><name>Ada</name></customer><line sku="A-1">2</line><line sku="B-2">1</line><status><Shipped><carrier>Post</carrier></Shipped></status><metadata><boxes>1</boxes><weight>3</weight></metadata></s:Order>"#,
+        xml
+    );
+}
+
+#[test]
+fn serialize_enum() {
+    #[derive(Serialize)]
+    enum Shape {
+        Circle(Circle),
+        Point,
+        Label(String),
+        Pair(u8, u8),
+    }
+
+    #[derive(Serialize)]
+    struct Circle {
+        #[serde(rename = "@r")]
+        r: u8,
+    }
+
+    let xml = flexml::to_xml(&vec![
+        Shape::Circle(Circle { r: 2 }),
+        Shape::Point,
+        Shape::Label("text".to_string()),
+        Shape::Pair(1, 2),
+    ])
+    .expect("Failed to serialize");
+
+    assert_eq!(
+        r#"<Circle r="2"/><Point/><Label>text</Label><Pair>1</Pair><Pair>2</Pair>"#,
+        xml.to_string()
+    );
+}
+
+#[test]
+fn serialize_unit_variant_attributes_and_flatten() {
+    #[derive(Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Book,
+        Film,
+    }
+
+    #[derive(Serialize)]
+    struct Ids {
+        #[serde(rename = "@id")]
+        id: u32,
+        #[serde(rename = "$value")]
+        title: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct Media {
+        #[serde(rename = "@kind")]
+        kind: Kind,
+        #[serde(rename = "@also")]
+        also: Vec<Kind>,
+        #[serde(flatten)]
+        ids: Ids,
+    }
+
+    #[derive(Serialize)]
+    struct Library {
+        media: Media,
+    }
+
+    let media = Media {
+        kind: Kind::Book,
+        also: vec![Kind::Film],
+        ids: Ids {
+            id: 3,
+            title: "Dune",
+        },
+    };
+
+    assert_xml_eq!(
+        r#"<Library><media kind="book" also="film" id="3">Dune</media></Library>"#,
+        flexml::to_xml(&Library { media }).expect("Failed to serialize")
+    );
+}
+
+#[test]
+fn serialize_errors() {
+    #[derive(Serialize)]
+    struct Unknown {
+        #[serde(rename = "Missing:value")]
+        value: u8,
+    }
+
+    #[derive(Serialize)]
+    struct Inner {
+        value: u8,
+    }
+
+    #[derive(Serialize)]
+    struct NestedAttribute {
+        #[serde(rename = "@value")]
+        value: Inner,
+    }
+
+    assert!(matches!(
+        flexml::to_xml(&Unknown { value: 0 }),
+        Err(XMLError::NamespaceNotFound(_))
+    ));
+    assert!(matches!(
+        flexml::to_xml(&NestedAttribute {
+            value: Inner { value: 0 }
+        }),
+        Err(XMLError::Other(_))
+    ));
+    assert!(matches!(
+        flexml::to_xml(&BTreeMap::from([("@id", 1)])),
+        Err(XMLError::Name(_))
+    ));
+    assert!(matches!(
+        flexml::to_xml(&BTreeMap::from([("two words", 1)])),
+        Err(XMLError::Name(_))
+    ));
+}
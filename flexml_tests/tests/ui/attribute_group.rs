@@ -0,0 +1,17 @@
+use flexml::macros::ToXMLAttributes;
+
+#[derive(ToXMLAttributes)]
+struct Group(u8, #[name("second")] u8);
+
+#[derive(ToXMLAttributes)]
+struct Mixed {
+    #[text]
+    text: String,
+}
+
+#[derive(ToXMLAttributes)]
+enum NotAStruct {
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use flexml::macros::ToXMLAttributeValue;
+
+#[derive(ToXMLAttributeValue)]
+enum Tagged {
+    Plain,
+    Sized(u8),
+}
+
+#[derive(ToXMLAttributeValue)]
+struct NotAnEnum;
+
+fn main() {}
@@ -0,0 +1,60 @@
+use flexml::macros::ToXML;
+
+#[derive(ToXML)]
+struct Price {
+    #[text]
+    #[attribute]
+    amount: u32,
+    #[name("Unit")]
+    #[text]
+    unit: String,
+}
+
+#[derive(ToXML)]
+struct Spread {
+    #[attributes]
+    #[with(to_string)]
+    group: Vec<flexml::XMLAttribute>,
+}
+
+#[derive(ToXML)]
+#[tag_attribute(xsi)]
+#[untagged]
+enum Ambiguous {
+    Variant,
+}
+
+#[derive(ToXML)]
+#[into(String)]
+#[with(to_string)]
+struct Delegated;
+
+#[derive(ToXML)]
+enum Converted {
+    #[with(to_string)]
+    Code(#[name("Code")] u8),
+}
+
+#[derive(ToXML)]
+struct Wrapped {
+    #[wrap("Names")]
+    #[text]
+    names: Vec<String>,
+    #[item_name("Item")]
+    #[name("Entry")]
+    entries: Vec<String>,
+    #[sequence]
+    #[attribute]
+    codes: Vec<u8>,
+    #[map(key_name)]
+    #[name("Settings")]
+    settings: std::collections::BTreeMap<String, u8>,
+    #[any]
+    #[case("snake_case")]
+    extensions: Vec<flexml::XML>,
+    #[any_attributes]
+    #[wrap]
+    extra: Vec<flexml::XMLAttribute>,
+}
+
+fn main() {}
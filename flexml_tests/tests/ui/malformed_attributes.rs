@@ -0,0 +1,31 @@
+use flexml::macros::ToXML;
+
+#[derive(ToXML)]
+#[declaration("1.x")]
+#[name]
+struct Root {
+    #[case(=)]
+    field: u8,
+    #[unserialized(true)]
+    skipped: u8,
+    #[with("not_an_ident")]
+    converted: u8,
+    #[with(1 + 1)]
+    element: u8,
+    #[attribute(with)]
+    formatted: u8,
+    #[wrap("Items", empty)]
+    items: Vec<u8>,
+    #[map(sorted)]
+    settings: std::collections::BTreeMap<u8, u8>,
+    #[map(key_name, entry = "Setting")]
+    options: std::collections::BTreeMap<u8, u8>,
+}
+
+#[derive(ToXML)]
+#[tag_attribute(kind)]
+enum Tagged {
+    Variant,
+}
+
+fn main() {}
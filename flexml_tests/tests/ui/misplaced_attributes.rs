@@ -0,0 +1,50 @@
+use flexml::macros::ToXML;
+
+#[derive(ToXML)]
+#[flatten]
+#[skip_if(Option::is_none)]
+struct Container {
+    #[untagged]
+    field: u8,
+}
+
+#[derive(ToXML)]
+enum Variants {
+    #[untagged]
+    Variant(u8),
+    #[unit_repr(true)]
+    NotUnit(u8),
+    #[skip_none]
+    Skipped(Option<u8>),
+}
+
+#[derive(ToXML)]
+#[tag_attribute("type")]
+struct Tagged {
+    field: u8,
+}
+
+#[derive(ToXML)]
+#[namespaces(("Kinds", "https://kinds.com/namespace"))]
+#[tag_attribute("kind")]
+enum Kinds {
+    #[namespace("Kinds")]
+    Namespaced,
+}
+
+#[derive(ToXML)]
+#[text]
+struct Plain {
+    field: u8,
+}
+
+#[derive(ToXML)]
+#[text]
+enum Level {
+    Low,
+    #[unit_repr("hi")]
+    High,
+    Custom(u8),
+}
+
+fn main() {}
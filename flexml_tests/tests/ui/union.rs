@@ -0,0 +1,9 @@
+use flexml::macros::ToXML;
+
+#[derive(ToXML)]
+union Root {
+    a: u8,
+    b: u16,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use flexml::macros::ToXML;
+
+#[derive(ToXML)]
+#[namespaces(("Declared", "https://declared.com/namespace"))]
+#[namespace("Declared")]
+#[case_all("Title Case")]
+struct Root {
+    #[namespace("Typo")]
+    field: u8,
+    #[case("CamelCaseish")]
+    other: u8,
+}
+
+#[derive(ToXML)]
+enum Variants {
+    #[namespace("Undeclared")]
+    Variant(u8),
+}
+
+fn main() {}
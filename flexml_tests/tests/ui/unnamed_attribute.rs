@@ -0,0 +1,12 @@
+use flexml::macros::ToXML;
+
+#[derive(ToXML)]
+struct Point(#[attribute] u8, #[attribute] #[name("y")] u8);
+
+#[derive(ToXML)]
+enum Farewell {
+    Bye(#[attribute] u8),
+    Later(#[attribute] #[name("in")] u8),
+}
+
+fn main() {}